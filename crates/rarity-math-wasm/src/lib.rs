@@ -0,0 +1,47 @@
+//! wasm-bindgen exports of `rarity-math`.
+//!
+//! Built into `packages/app/src/wasm/rarity-math` by the app's `build:wasm`
+//! script (run by `dev`, `build` and `typecheck`); `magicblock/rarity.ts`
+//! wraps it for the app.
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct RarityBonus {
+    pub combat: u8,
+    pub movement: u8,
+    pub vision: u8,
+}
+
+/// Reduce 32 bytes of VRF output to a roll in basis points, [0, 9999].
+#[wasm_bindgen(js_name = rollFromRandomness)]
+pub fn roll_from_randomness(randomness: &[u8]) -> Result<u16, JsError> {
+    let randomness: &[u8; 32] = randomness
+        .try_into()
+        .map_err(|_| JsError::new("randomness must be 32 bytes"))?;
+    Ok(rarity_math::roll_from_randomness(randomness))
+}
+
+/// Map a roll to its tier index. Uses the launch table when `thresholds` is empty.
+#[wasm_bindgen(js_name = tierForRoll)]
+pub fn tier_for_roll(thresholds: &[u16], roll: u16) -> Result<u8, JsError> {
+    let thresholds = if thresholds.is_empty() {
+        &rarity_math::DEFAULT_RARITY_THRESHOLDS[..]
+    } else {
+        thresholds
+    };
+    if !rarity_math::is_valid_table(thresholds) {
+        return Err(JsError::new("invalid rarity table"));
+    }
+    Ok(rarity_math::tier_for_roll(thresholds, roll))
+}
+
+/// Bonuses for a tier index, `undefined` past Legendary.
+#[wasm_bindgen(js_name = bonusForTier)]
+pub fn bonus_for_tier(tier: u8) -> Option<RarityBonus> {
+    rarity_math::bonus_for_tier(tier).map(|b| RarityBonus {
+        combat: b.combat,
+        movement: b.movement,
+        vision: b.vision,
+    })
+}
@@ -0,0 +1,321 @@
+//! Randomness-to-rarity mapping shared by the on-chain `vrf-rarity` program
+//! and the app (through `rarity-math-wasm`), so both resolve a roll to the
+//! same tier and bonus.
+//!
+//! Tiers are indexed 0=Common 1=Uncommon 2=Rare 3=Epic 4=Legendary. Rolls
+//! and tier bounds are in basis points, so odds like 0.5% are expressible.
+
+#![no_std]
+
+/// Rolls are uniform in [0, ROLL_DENOMINATOR).
+pub const ROLL_DENOMINATOR: u16 = 10_000;
+
+/// Highest value produced by a roll.
+pub const ROLL_MAX: u16 = ROLL_DENOMINATOR - 1;
+
+/// Number of rarity tiers in the launch table.
+pub const TIER_COUNT: usize = 5;
+
+/// Launch odds in basis points, mirrors `RARITY_WEIGHTS` (percent) in game-core.
+pub const RARITY_WEIGHTS: [u16; TIER_COUNT] = [5_000, 3_000, 1_500, 400, 100];
+
+/// Inclusive upper roll bound per tier for `RARITY_WEIGHTS`.
+pub const DEFAULT_RARITY_THRESHOLDS: [u16; TIER_COUNT] = [4_999, 7_999, 9_499, 9_899, 9_999];
+
+/// Stat bonuses granted by a rarity tier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RarityBonus {
+    pub combat: u8,
+    pub movement: u8,
+    pub vision: u8,
+}
+
+/// Per-tier bonuses, mirrors `RARITY_BONUSES` in game-core.
+pub const RARITY_BONUSES: [RarityBonus; TIER_COUNT] = [
+    RarityBonus {
+        combat: 0,
+        movement: 0,
+        vision: 0,
+    },
+    RarityBonus {
+        combat: 2,
+        movement: 0,
+        vision: 0,
+    },
+    RarityBonus {
+        combat: 5,
+        movement: 0,
+        vision: 1,
+    },
+    RarityBonus {
+        combat: 10,
+        movement: 1,
+        vision: 1,
+    },
+    RarityBonus {
+        combat: 20,
+        movement: 1,
+        vision: 2,
+    },
+];
+
+/// Sample a value uniform in [0, bound) from 32 bytes of VRF output.
+///
+/// Reads the randomness as four little-endian u64 words and takes the first
+/// one below the largest multiple of `bound`, which removes modulo bias. A
+/// word is only rejected with probability below `bound / 2^64`; if all four
+/// are, the last word is reduced directly.
+pub fn uniform_below(randomness: &[u8; 32], bound: u64) -> u64 {
+    debug_assert!(bound > 0);
+    let zone = u64::MAX - (u64::MAX % bound);
+
+    let mut word = 0;
+    for chunk in randomness.chunks_exact(8) {
+        word = u64::from_le_bytes(chunk.try_into().unwrap());
+        if word < zone {
+            break;
+        }
+    }
+    word % bound
+}
+
+/// Reduce 32 bytes of VRF output to a roll in [0, ROLL_MAX].
+pub fn roll_from_randomness(randomness: &[u8; 32]) -> u16 {
+    uniform_below(randomness, ROLL_DENOMINATOR as u64) as u16
+}
+
+/// A table is valid when it has at least one bound, bounds are strictly
+/// increasing, and the last one is `ROLL_MAX` so every roll maps to a tier.
+pub fn is_valid_table(thresholds: &[u16]) -> bool {
+    !thresholds.is_empty()
+        && thresholds.windows(2).all(|w| w[0] < w[1])
+        && thresholds[thresholds.len() - 1] == ROLL_MAX
+}
+
+/// Map a roll to its tier index in a valid table.
+pub fn tier_for_roll(thresholds: &[u16], roll: u16) -> u8 {
+    thresholds
+        .iter()
+        .position(|&bound| roll <= bound)
+        .unwrap_or(thresholds.len().saturating_sub(1)) as u8
+}
+
+/// Width in basis points of `tier` in a valid table, i.e. the weight that
+/// makes `pick_weighted` agree with `tier_for_roll`.
+pub fn tier_weight(thresholds: &[u16], tier: usize) -> u16 {
+    match tier {
+        0 => thresholds[0] + 1,
+        _ => thresholds[tier] - thresholds[tier - 1],
+    }
+}
+
+/// Pass a `numerator / denominator` chance check. `denominator` must be
+/// non-zero; a numerator at or above it always passes.
+pub fn passes_check(randomness: &[u8; 32], numerator: u32, denominator: u32) -> bool {
+    uniform_below(randomness, denominator as u64) < numerator as u64
+}
+
+/// Pick an index from `weights` with probability proportional to its weight.
+/// Returns `None` if all weights are zero.
+pub fn pick_weighted(weights: &[u16], randomness: &[u8; 32]) -> Option<usize> {
+    let total: u64 = weights.iter().map(|&w| w as u64).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut roll = uniform_below(randomness, total);
+    for (i, &weight) in weights.iter().enumerate() {
+        if roll < weight as u64 {
+            return Some(i);
+        }
+        roll -= weight as u64;
+    }
+    None
+}
+
+/// Bonuses for a tier index, `None` past Legendary.
+pub fn bonus_for_tier(tier: u8) -> Option<RarityBonus> {
+    RARITY_BONUSES.get(tier as usize).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_table_is_valid() {
+        assert!(is_valid_table(&DEFAULT_RARITY_THRESHOLDS));
+    }
+
+    #[test]
+    fn default_table_matches_weights_over_every_roll() {
+        let mut counts = [0u16; TIER_COUNT];
+        for roll in 0..=ROLL_MAX {
+            counts[tier_for_roll(&DEFAULT_RARITY_THRESHOLDS, roll) as usize] += 1;
+        }
+        assert_eq!(counts, RARITY_WEIGHTS);
+    }
+
+    #[test]
+    fn weights_sum_to_denominator() {
+        assert_eq!(RARITY_WEIGHTS.iter().sum::<u16>(), ROLL_DENOMINATOR);
+    }
+
+    #[test]
+    fn tiers_are_monotonic_in_roll() {
+        let mut prev = 0;
+        for roll in 0..=ROLL_MAX {
+            let tier = tier_for_roll(&DEFAULT_RARITY_THRESHOLDS, roll);
+            assert!(
+                tier >= prev,
+                "roll {roll} dropped from tier {prev} to {tier}"
+            );
+            prev = tier;
+        }
+    }
+
+    #[test]
+    fn tier_boundaries() {
+        let cases = [
+            (0, 0),
+            (4_999, 0),
+            (5_000, 1),
+            (7_999, 1),
+            (8_000, 2),
+            (9_499, 2),
+            (9_500, 3),
+            (9_899, 3),
+            (9_900, 4),
+            (9_999, 4),
+        ];
+        for (roll, tier) in cases {
+            assert_eq!(
+                tier_for_roll(&DEFAULT_RARITY_THRESHOLDS, roll),
+                tier,
+                "roll {roll}"
+            );
+        }
+    }
+
+    #[test]
+    fn half_percent_tiers_are_expressible() {
+        // 0.5% Legendary, 2.5% Epic
+        let table = [4_999, 7_999, 9_699, 9_949, 9_999];
+        assert!(is_valid_table(&table));
+        let legendary = (0..=ROLL_MAX)
+            .filter(|&r| tier_for_roll(&table, r) == 4)
+            .count();
+        let epic = (0..=ROLL_MAX)
+            .filter(|&r| tier_for_roll(&table, r) == 3)
+            .count();
+        assert_eq!(legendary, 50);
+        assert_eq!(epic, 250);
+    }
+
+    #[test]
+    fn rejects_invalid_tables() {
+        assert!(!is_valid_table(&[]));
+        assert!(!is_valid_table(&[4_999, 7_999, 9_499, 9_899]));
+        assert!(!is_valid_table(&[4_999, 4_999, 9_999]));
+        assert!(!is_valid_table(&[7_999, 4_999, 9_999]));
+        assert!(is_valid_table(&[9_999]));
+    }
+
+    fn randomness_with_words(words: [u64; 4]) -> [u8; 32] {
+        let mut randomness = [0u8; 32];
+        for (chunk, word) in randomness.chunks_exact_mut(8).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        randomness
+    }
+
+    #[test]
+    fn every_roll_value_is_reachable_once_per_denominator() {
+        let mut counts = [0u8; ROLL_DENOMINATOR as usize];
+        for word in 0..ROLL_DENOMINATOR as u64 * 3 {
+            counts[roll_from_randomness(&randomness_with_words([word, 0, 0, 0])) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| c == 3));
+    }
+
+    #[test]
+    fn rejects_words_in_the_biased_zone() {
+        let zone = u64::MAX - (u64::MAX % ROLL_DENOMINATOR as u64);
+        let randomness = randomness_with_words([zone, u64::MAX, 12_345, 7]);
+        assert_eq!(roll_from_randomness(&randomness), 2_345);
+
+        let all_biased = randomness_with_words([zone; 4]);
+        assert_eq!(
+            roll_from_randomness(&all_biased),
+            (zone % ROLL_DENOMINATOR as u64) as u16
+        );
+    }
+
+    #[test]
+    fn uniform_below_respects_bound() {
+        for bound in [1, 2, 3, 100, 10_000, u64::MAX] {
+            for seed in 0..=255u8 {
+                let randomness = [seed; 32];
+                assert!(uniform_below(&randomness, bound) < bound);
+            }
+        }
+    }
+
+    #[test]
+    fn pick_weighted_matches_weights_over_every_roll() {
+        let weights = [30, 15, 20, 20, 15];
+        let mut counts = [0u16; 5];
+        for word in 0..100u64 {
+            let index = pick_weighted(&weights, &randomness_with_words([word, 0, 0, 0]));
+            counts[index.unwrap()] += 1;
+        }
+        assert_eq!(counts, weights);
+    }
+
+    #[test]
+    fn pick_weighted_skips_zero_weights() {
+        let weights = [0, 5, 0, 5];
+        for word in 0..10u64 {
+            let index = pick_weighted(&weights, &randomness_with_words([word, 0, 0, 0])).unwrap();
+            assert!(index == 1 || index == 3);
+        }
+        assert_eq!(pick_weighted(&[0, 0], &[7; 32]), None);
+        assert_eq!(pick_weighted(&[], &[7; 32]), None);
+    }
+
+    #[test]
+    fn tier_weights_pick_the_same_tier_as_thresholds() {
+        let weights: [u16; TIER_COUNT] =
+            core::array::from_fn(|tier| tier_weight(&DEFAULT_RARITY_THRESHOLDS, tier));
+        assert_eq!(weights, RARITY_WEIGHTS);
+
+        for seed in 0..=255u8 {
+            let randomness = [seed; 32];
+            let roll = roll_from_randomness(&randomness);
+            assert_eq!(
+                pick_weighted(&weights, &randomness),
+                Some(tier_for_roll(&DEFAULT_RARITY_THRESHOLDS, roll) as usize)
+            );
+        }
+    }
+
+    #[test]
+    fn passes_check_matches_numerator_over_every_roll() {
+        let passes = (0..100u64)
+            .filter(|&word| passes_check(&randomness_with_words([word, 0, 0, 0]), 37, 100))
+            .count();
+        assert_eq!(passes, 37);
+        assert!(!passes_check(&[9; 32], 0, 100));
+        assert!(passes_check(&[9; 32], 100, 100));
+    }
+
+    #[test]
+    fn bonuses_grow_with_tier() {
+        for tier in 1..TIER_COUNT as u8 {
+            let lo = bonus_for_tier(tier - 1).unwrap();
+            let hi = bonus_for_tier(tier).unwrap();
+            assert!(hi.combat > lo.combat);
+            assert!(hi.movement >= lo.movement && hi.vision >= lo.vision);
+        }
+        assert_eq!(bonus_for_tier(TIER_COUNT as u8), None);
+    }
+}
@@ -0,0 +1,19 @@
+use solana_rpc_client_api::client_error::Error as ClientError;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("rpc error: {0}")]
+    Rpc(Box<ClientError>),
+    #[error("failed to decode account: {0}")]
+    Decode(#[from] anchor_lang::error::Error),
+    #[error("rarity result not fulfilled within timeout")]
+    Timeout,
+}
+
+impl From<ClientError> for Error {
+    fn from(err: ClientError) -> Self {
+        Error::Rpc(Box::new(err))
+    }
+}
@@ -0,0 +1,591 @@
+//! Typed instruction builders. Callback instructions are invoked by the VRF
+//! oracle and have no builder here.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use anchor_spl::token_2022;
+use ephemeral_vrf_sdk::consts::{DEFAULT_QUEUE, VRF_PROGRAM_ID};
+use vrf_rarity::{
+    accounts, instruction, AchievementCondition, GameSummary, Tribe, UnitClass, ID, SOAR_PROGRAM_ID,
+};
+
+use crate::pda::*;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Roll for the unit minted in `settlement_id` on `turn` of game `game_id`.
+pub fn roll_rarity(
+    payer: &Pubkey,
+    game_id: u64,
+    nonce: u64,
+    unit_class: UnitClass,
+    turn: u16,
+    settlement_id: u32,
+    unit_type: u8,
+) -> Instruction {
+    build(
+        accounts::RollRarityCtx {
+            payer: *payer,
+            game_context: game_context_address(payer, game_id),
+            rarity_result: rarity_result_address(payer, nonce),
+            config: config_address(),
+            player_stats: player_stats_address(payer),
+            roll_ledger: roll_ledger_address(payer),
+            oracle_queue: DEFAULT_QUEUE,
+            program_identity: program_identity_address(),
+            vrf_program: VRF_PROGRAM_ID,
+            slot_hashes: sysvar::slot_hashes::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RollRarity {
+            nonce,
+            unit_class,
+            turn,
+            settlement_id,
+            unit_type,
+        },
+    )
+}
+
+pub fn roll_rarity_batch(
+    payer: &Pubkey,
+    game_id: u64,
+    nonce: u64,
+    count: u8,
+    unit_class: UnitClass,
+    turn: u16,
+) -> Instruction {
+    build(
+        accounts::RollRarityBatchCtx {
+            payer: *payer,
+            game_context: game_context_address(payer, game_id),
+            batch_result: rarity_batch_address(payer, nonce),
+            config: config_address(),
+            player_stats: player_stats_address(payer),
+            roll_ledger: roll_ledger_address(payer),
+            oracle_queue: DEFAULT_QUEUE,
+            program_identity: program_identity_address(),
+            vrf_program: VRF_PROGRAM_ID,
+            slot_hashes: sysvar::slot_hashes::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RollRarityBatch {
+            nonce,
+            count,
+            unit_class,
+            turn,
+        },
+    )
+}
+
+pub fn consume_roll(player: &Pubkey, nonce: u64, unit_id: u32) -> Instruction {
+    build(
+        accounts::ConsumeRollCtx {
+            player: *player,
+            rarity_result: rarity_result_address(player, nonce),
+            roll_ledger: roll_ledger_address(player),
+        },
+        instruction::ConsumeRoll { unit_id },
+    )
+}
+
+pub fn consume_batch_roll(
+    player: &Pubkey,
+    nonce: u64,
+    slot_index: u8,
+    unit_id: u32,
+) -> Instruction {
+    build(
+        accounts::ConsumeBatchRollCtx {
+            player: *player,
+            batch_result: rarity_batch_address(player, nonce),
+            roll_ledger: roll_ledger_address(player),
+        },
+        instruction::ConsumeBatchRoll {
+            slot_index,
+            unit_id,
+        },
+    )
+}
+
+pub fn close_rarity_result(player: &Pubkey, nonce: u64) -> Instruction {
+    build(
+        accounts::CloseRarityResultCtx {
+            player: *player,
+            rarity_result: rarity_result_address(player, nonce),
+        },
+        instruction::CloseRarityResult {},
+    )
+}
+
+/// Close a baseline-layout (v1) rarity result left over from before the
+/// version byte was added.
+pub fn close_legacy_rarity_result(player: &Pubkey, nonce: u64) -> Instruction {
+    build(
+        accounts::CloseLegacyRarityResultCtx {
+            player: *player,
+            legacy_result: rarity_result_address(player, nonce),
+        },
+        instruction::CloseLegacyRarityResult { _nonce: nonce },
+    )
+}
+
+pub fn close_rarity_batch(player: &Pubkey, nonce: u64) -> Instruction {
+    build(
+        accounts::CloseRarityBatchCtx {
+            player: *player,
+            batch_result: rarity_batch_address(player, nonce),
+        },
+        instruction::CloseRarityBatch {},
+    )
+}
+
+pub fn cancel_stale_roll(player: &Pubkey, nonce: u64) -> Instruction {
+    build(
+        accounts::CancelStaleRollCtx {
+            player: *player,
+            rarity_result: rarity_result_address(player, nonce),
+            player_stats: player_stats_address(player),
+            roll_ledger: roll_ledger_address(player),
+        },
+        instruction::CancelStaleRoll {},
+    )
+}
+
+pub fn cancel_stale_batch(player: &Pubkey, nonce: u64) -> Instruction {
+    build(
+        accounts::CancelStaleBatchCtx {
+            player: *player,
+            batch_result: rarity_batch_address(player, nonce),
+            player_stats: player_stats_address(player),
+            roll_ledger: roll_ledger_address(player),
+        },
+        instruction::CancelStaleBatch {},
+    )
+}
+
+pub fn start_game(
+    payer: &Pubkey,
+    game_id: u64,
+    tribe: Tribe,
+    map_width: u8,
+    map_height: u8,
+    player_count: u8,
+) -> Instruction {
+    build(
+        accounts::StartGameCtx {
+            payer: *payer,
+            game_session: game_session_address(payer, game_id),
+            oracle_queue: DEFAULT_QUEUE,
+            program_identity: program_identity_address(),
+            vrf_program: VRF_PROGRAM_ID,
+            slot_hashes: sysvar::slot_hashes::ID,
+            system_program: system_program::ID,
+        },
+        instruction::StartGame {
+            game_id,
+            tribe,
+            map_width,
+            map_height,
+            player_count,
+        },
+    )
+}
+
+/// Roll the reward for lootbox `lootbox_id` in game `game_id`.
+pub fn roll_lootbox(payer: &Pubkey, game_id: u64, lootbox_id: u32) -> Instruction {
+    let game_session = game_session_address(payer, game_id);
+    build(
+        accounts::RollLootboxCtx {
+            payer: *payer,
+            game_session,
+            lootbox_result: lootbox_result_address(&game_session, lootbox_id),
+            config: config_address(),
+            oracle_queue: DEFAULT_QUEUE,
+            program_identity: program_identity_address(),
+            vrf_program: VRF_PROGRAM_ID,
+            slot_hashes: sysvar::slot_hashes::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RollLootbox { lootbox_id },
+    )
+}
+
+/// Roll the golden age effect on the game context's current turn and era.
+pub fn roll_golden_age_effect(payer: &Pubkey, game_id: u64, turn: u16) -> Instruction {
+    let game_session = game_session_address(payer, game_id);
+    build(
+        accounts::RollGoldenAgeEffectCtx {
+            payer: *payer,
+            game_session,
+            game_context: game_context_address(payer, game_id),
+            golden_age_result: golden_age_result_address(&game_session, turn),
+            oracle_queue: DEFAULT_QUEUE,
+            program_identity: program_identity_address(),
+            vrf_program: VRF_PROGRAM_ID,
+            slot_hashes: sysvar::slot_hashes::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RollGoldenAgeEffect {},
+    )
+}
+
+/// Roll a `numerator / denominator` chance check for `tag` on `turn`.
+pub fn roll_probability_check(
+    payer: &Pubkey,
+    game_id: u64,
+    tag: u32,
+    turn: u16,
+    numerator: u32,
+    denominator: u32,
+) -> Instruction {
+    let game_session = game_session_address(payer, game_id);
+    build(
+        accounts::RollProbabilityCheckCtx {
+            payer: *payer,
+            game_session,
+            game_context: game_context_address(payer, game_id),
+            probability_check: probability_check_address(&game_session, tag, turn),
+            oracle_queue: DEFAULT_QUEUE,
+            program_identity: program_identity_address(),
+            vrf_program: VRF_PROGRAM_ID,
+            slot_hashes: sysvar::slot_hashes::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RollProbabilityCheck {
+            tag,
+            turn,
+            numerator,
+            denominator,
+        },
+    )
+}
+
+/// Mint the collectible for an Epic or better roll into the player's
+/// Token-2022 associated token account.
+pub fn mint_unit_collectible(payer: &Pubkey, game_id: u64, nonce: u64) -> Instruction {
+    let rarity_result = rarity_result_address(payer, nonce);
+    let collectible_mint = collectible_mint_address(&rarity_result);
+    build(
+        accounts::MintUnitCollectibleCtx {
+            payer: *payer,
+            rarity_result,
+            game_session: game_session_address(payer, game_id),
+            collectible_mint,
+            player_token_account: get_associated_token_address_with_program_id(
+                payer,
+                &collectible_mint,
+                &token_2022::ID,
+            ),
+            token_program: token_2022::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::MintUnitCollectible {},
+    )
+}
+
+/// Pick an outcome from weighted table `table_id`.
+pub fn roll_weighted(payer: &Pubkey, table_id: u32, nonce: u64) -> Instruction {
+    build(
+        accounts::RollWeightedCtx {
+            payer: *payer,
+            weighted_table: weighted_table_address(table_id),
+            weighted_roll_result: weighted_roll_result_address(payer, nonce),
+            oracle_queue: DEFAULT_QUEUE,
+            program_identity: program_identity_address(),
+            vrf_program: VRF_PROGRAM_ID,
+            slot_hashes: sysvar::slot_hashes::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RollWeighted { nonce },
+    )
+}
+
+pub fn cancel_stale_weighted_roll(player: &Pubkey, nonce: u64) -> Instruction {
+    build(
+        accounts::CancelStaleWeightedRollCtx {
+            player: *player,
+            weighted_roll_result: weighted_roll_result_address(player, nonce),
+        },
+        instruction::CancelStaleWeightedRoll {},
+    )
+}
+
+/// SOAR game accounts `submit_score` forwards to the leaderboard program.
+/// The player's profile and score list are derived from these.
+pub struct SoarScoreAccounts {
+    pub game: Pubkey,
+    pub leaderboard: Pubkey,
+    pub top_entries: Pubkey,
+}
+
+/// Record the end-of-game summary for `game_id`.
+pub fn finalize_game(player: &Pubkey, game_id: u64, summary: GameSummary) -> Instruction {
+    build(
+        accounts::FinalizeGameCtx {
+            player: *player,
+            game_session: game_session_address(player, game_id),
+            game_record: game_record_address(player, game_id),
+            system_program: system_program::ID,
+        },
+        instruction::FinalizeGame { game_id, summary },
+    )
+}
+
+/// Submit the floor price from `game_id`'s `GameRecord` to the SOAR
+/// leaderboard.
+pub fn submit_score(player: &Pubkey, game_id: u64, soar: &SoarScoreAccounts) -> Instruction {
+    let soar_player_account = soar_player_address(player);
+    build(
+        accounts::SubmitScoreCtx {
+            player: *player,
+            score_submission: score_submission_address(player, game_id),
+            game_record: game_record_address(player, game_id),
+            soar_authority: soar_authority_address(),
+            soar_player_account,
+            soar_game: soar.game,
+            soar_leaderboard: soar.leaderboard,
+            soar_player_scores: soar_player_scores_address(&soar_player_account, &soar.leaderboard),
+            soar_top_entries: soar.top_entries,
+            soar_program: SOAR_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        instruction::SubmitScore { game_id },
+    )
+}
+
+/// Record a verifier-attested score for `game_id`. Must directly follow an
+/// Ed25519 precompile instruction over `vrf_rarity::attestation_message`.
+pub fn attest_result(
+    player: &Pubkey,
+    game_id: u64,
+    action_log_hash: [u8; 32],
+    score: u64,
+) -> Instruction {
+    build(
+        accounts::AttestResultCtx {
+            player: *player,
+            game_session: game_session_address(player, game_id),
+            verifier_registry: verifier_registry_address(),
+            attested_result: attested_result_address(player, game_id),
+            instructions: sysvar::instructions::ID,
+            system_program: system_program::ID,
+        },
+        instruction::AttestResult {
+            game_id,
+            action_log_hash,
+            score,
+        },
+    )
+}
+
+/// Commit `turn`'s action hash to `game_id`'s action chain.
+pub fn commit_turn(player: &Pubkey, game_id: u64, turn: u16, action_hash: [u8; 32]) -> Instruction {
+    build(
+        accounts::CommitTurnCtx {
+            player: *player,
+            action_chain: action_chain_address(player, game_id),
+            system_program: system_program::ID,
+        },
+        instruction::CommitTurn {
+            game_id,
+            turn,
+            action_hash,
+        },
+    )
+}
+
+/// Claim `achievement_id` using the stats recorded for `game_id`.
+pub fn claim_achievement(player: &Pubkey, achievement_id: u16, game_id: u64) -> Instruction {
+    let achievement = achievement_address(achievement_id);
+    build(
+        accounts::ClaimAchievementCtx {
+            player: *player,
+            achievement,
+            game_record: game_record_address(player, game_id),
+            achievement_unlock: achievement_unlock_address(&achievement, player),
+            system_program: system_program::ID,
+        },
+        instruction::ClaimAchievement { game_id },
+    )
+}
+
+pub fn open_game_context(player: &Pubkey, game_id: u64) -> Instruction {
+    build(
+        accounts::OpenGameContextCtx {
+            player: *player,
+            game_context: game_context_address(player, game_id),
+            system_program: system_program::ID,
+        },
+        instruction::OpenGameContext { game_id },
+    )
+}
+
+pub fn advance_game_turn(player: &Pubkey, game_id: u64, turn: u16, era: u8) -> Instruction {
+    build(
+        accounts::UpdateGameContextCtx {
+            player: *player,
+            game_context: game_context_address(player, game_id),
+            action_chain: action_chain_address(player, game_id),
+        },
+        instruction::AdvanceGameTurn { turn, era },
+    )
+}
+
+pub fn close_game_context(player: &Pubkey, game_id: u64) -> Instruction {
+    build(
+        accounts::CloseGameContextCtx {
+            player: *player,
+            game_context: game_context_address(player, game_id),
+        },
+        instruction::CloseGameContext {},
+    )
+}
+
+pub fn initialize_config(authority: &Pubkey, thresholds: Vec<u16>) -> Instruction {
+    build(
+        accounts::InitializeConfigCtx {
+            authority: *authority,
+            config: config_address(),
+            program: ID,
+            program_data: program_data_address(),
+            system_program: system_program::ID,
+        },
+        instruction::InitializeConfig { thresholds },
+    )
+}
+
+pub fn update_config(
+    authority: &Pubkey,
+    unit_class: UnitClass,
+    thresholds: Vec<u16>,
+) -> Instruction {
+    build(
+        accounts::UpdateRarityTableCtx {
+            authority: *authority,
+            config: config_address(),
+            weighted_table: weighted_table_address(unit_class.weighted_table_id()),
+        },
+        instruction::UpdateConfig {
+            unit_class,
+            thresholds,
+        },
+    )
+}
+
+/// Register `unit_class`'s rarity odds as weighted table `unit_class as u32`.
+pub fn register_rarity_table(authority: &Pubkey, unit_class: UnitClass) -> Instruction {
+    build(
+        accounts::RegisterRarityTableCtx {
+            authority: *authority,
+            config: config_address(),
+            weighted_table: weighted_table_address(unit_class.weighted_table_id()),
+            system_program: system_program::ID,
+        },
+        instruction::RegisterRarityTable { unit_class },
+    )
+}
+
+pub fn update_pity_threshold(authority: &Pubkey, pity_threshold: u8) -> Instruction {
+    build(
+        accounts::UpdateConfigCtx {
+            authority: *authority,
+            config: config_address(),
+        },
+        instruction::UpdatePityThreshold { pity_threshold },
+    )
+}
+
+pub fn create_weighted_table(
+    authority: &Pubkey,
+    table_id: u32,
+    weights: Vec<u16>,
+    labels: Vec<String>,
+) -> Instruction {
+    build(
+        accounts::CreateWeightedTableCtx {
+            authority: *authority,
+            config: config_address(),
+            weighted_table: weighted_table_address(table_id),
+            system_program: system_program::ID,
+        },
+        instruction::CreateWeightedTable {
+            table_id,
+            weights,
+            labels,
+        },
+    )
+}
+
+pub fn update_weighted_table(
+    authority: &Pubkey,
+    table_id: u32,
+    weights: Vec<u16>,
+    labels: Vec<String>,
+) -> Instruction {
+    build(
+        accounts::UpdateWeightedTableCtx {
+            authority: *authority,
+            weighted_table: weighted_table_address(table_id),
+        },
+        instruction::UpdateWeightedTable { weights, labels },
+    )
+}
+
+pub fn set_verifiers(authority: &Pubkey, verifiers: Vec<Pubkey>) -> Instruction {
+    build(
+        accounts::SetVerifiersCtx {
+            authority: *authority,
+            config: config_address(),
+            verifier_registry: verifier_registry_address(),
+            system_program: system_program::ID,
+        },
+        instruction::SetVerifiers { verifiers },
+    )
+}
+
+pub fn create_achievement(
+    authority: &Pubkey,
+    achievement_id: u16,
+    slug: String,
+    condition: AchievementCondition,
+) -> Instruction {
+    build(
+        accounts::CreateAchievementCtx {
+            authority: *authority,
+            config: config_address(),
+            achievement: achievement_address(achievement_id),
+            system_program: system_program::ID,
+        },
+        instruction::CreateAchievement {
+            achievement_id,
+            slug,
+            condition,
+        },
+    )
+}
+
+pub fn update_achievement(
+    authority: &Pubkey,
+    achievement_id: u16,
+    condition: AchievementCondition,
+    active: bool,
+) -> Instruction {
+    build(
+        accounts::UpdateAchievementCtx {
+            authority: *authority,
+            achievement: achievement_address(achievement_id),
+        },
+        instruction::UpdateAchievement { condition, active },
+    )
+}
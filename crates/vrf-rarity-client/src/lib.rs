@@ -0,0 +1,17 @@
+//! Rust client for the `vrf-rarity` program.
+//!
+//! Mirrors `packages/app/src/magicblock/vrf.ts` for backend tools, bots and
+//! test harnesses: PDA derivation, instruction builders, account decoding and
+//! a poll-for-fulfillment helper over `RpcClient`.
+
+pub mod error;
+pub mod instructions;
+pub mod pda;
+pub mod poll;
+pub mod state;
+
+pub use error::{Error, Result};
+pub use pda::*;
+pub use poll::*;
+pub use state::*;
+pub use vrf_rarity::ID as PROGRAM_ID;
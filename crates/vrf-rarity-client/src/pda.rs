@@ -0,0 +1,215 @@
+//! PDA derivation, matching the seeds declared in the program's account contexts.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::bpf_loader_upgradeable::get_program_data_address;
+use vrf_rarity::{
+    ACHIEVEMENT_SEED, ACHIEVEMENT_UNLOCK_SEED, ACTION_CHAIN_SEED, ATTESTED_RESULT_SEED,
+    COLLECTIBLE_SEED, CONFIG_SEED, GAME_CONTEXT_SEED, GAME_RECORD_SEED, GAME_SESSION_SEED,
+    GOLDEN_AGE_SEED, ID, LOOTBOX_SEED, PLAYER_STATS_SEED, PROBABILITY_CHECK_SEED,
+    RARITY_BATCH_SEED, RARITY_SEED, ROLL_LEDGER_SEED, SCORE_SUBMISSION_SEED, SOAR_AUTHORITY_SEED,
+    SOAR_PLAYER_SCORES_SEED, SOAR_PLAYER_SEED, SOAR_PROGRAM_ID, VERIFIER_REGISTRY_SEED,
+    WEIGHTED_ROLL_SEED, WEIGHTED_TABLE_SEED,
+};
+
+/// `RarityResult` PDA: `[b"rarity", player, nonce_le]`.
+pub fn rarity_result_address(player: &Pubkey, nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(&[RARITY_SEED, player.as_ref(), &nonce.to_le_bytes()], &ID).0
+}
+
+/// `RarityBatchResult` PDA: `[b"rarity_batch", player, nonce_le]`.
+pub fn rarity_batch_address(player: &Pubkey, nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[RARITY_BATCH_SEED, player.as_ref(), &nonce.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+/// Singleton `RarityConfig` PDA: `[b"config"]`.
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[CONFIG_SEED], &ID).0
+}
+
+/// `PlayerRollStats` PDA: `[b"player_stats", player]`.
+pub fn player_stats_address(player: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PLAYER_STATS_SEED, player.as_ref()], &ID).0
+}
+
+/// `RollLedger` PDA: `[b"roll_ledger", player]`.
+pub fn roll_ledger_address(player: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[ROLL_LEDGER_SEED, player.as_ref()], &ID).0
+}
+
+/// `GameSession` PDA: `[b"game_session", player, game_id_le]`.
+pub fn game_session_address(player: &Pubkey, game_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[GAME_SESSION_SEED, player.as_ref(), &game_id.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+/// `GameContext` PDA: `[b"game_context", player, game_id_le]`.
+pub fn game_context_address(player: &Pubkey, game_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[GAME_CONTEXT_SEED, player.as_ref(), &game_id.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+/// `LootboxResult` PDA: `[b"lootbox", game_session, lootbox_id_le]`.
+pub fn lootbox_result_address(game_session: &Pubkey, lootbox_id: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            LOOTBOX_SEED,
+            game_session.as_ref(),
+            &lootbox_id.to_le_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// `GoldenAgeResult` PDA: `[b"golden_age", game_session, turn_le]`.
+pub fn golden_age_result_address(game_session: &Pubkey, turn: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[GOLDEN_AGE_SEED, game_session.as_ref(), &turn.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+/// `ProbabilityCheck` PDA: `[b"probability_check", game_session, tag_le, turn_le]`.
+pub fn probability_check_address(game_session: &Pubkey, tag: u32, turn: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            PROBABILITY_CHECK_SEED,
+            game_session.as_ref(),
+            &tag.to_le_bytes(),
+            &turn.to_le_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Collectible Token-2022 mint PDA: `[b"collectible", rarity_result]`.
+pub fn collectible_mint_address(rarity_result: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[COLLECTIBLE_SEED, rarity_result.as_ref()], &ID).0
+}
+
+/// `WeightedTable` PDA: `[b"weighted_table", table_id_le]`.
+pub fn weighted_table_address(table_id: u32) -> Pubkey {
+    Pubkey::find_program_address(&[WEIGHTED_TABLE_SEED, &table_id.to_le_bytes()], &ID).0
+}
+
+/// `WeightedRollResult` PDA: `[b"weighted_roll", player, nonce_le]`.
+pub fn weighted_roll_result_address(player: &Pubkey, nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[WEIGHTED_ROLL_SEED, player.as_ref(), &nonce.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+/// `ScoreSubmission` PDA: `[b"score_submission", player, game_id_le]`.
+pub fn score_submission_address(player: &Pubkey, game_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            SCORE_SUBMISSION_SEED,
+            player.as_ref(),
+            &game_id.to_le_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// The PDA registered as SOAR game authority: `[b"soar_authority"]`.
+pub fn soar_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[SOAR_AUTHORITY_SEED], &ID).0
+}
+
+/// SOAR player profile: `[b"player", user]` under the SOAR program.
+pub fn soar_player_address(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[SOAR_PLAYER_SEED, user.as_ref()], &SOAR_PROGRAM_ID).0
+}
+
+/// SOAR score list: `[b"player-scores-list", player_account, leaderboard]`
+/// under the SOAR program.
+pub fn soar_player_scores_address(player_account: &Pubkey, leaderboard: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            SOAR_PLAYER_SCORES_SEED,
+            player_account.as_ref(),
+            leaderboard.as_ref(),
+        ],
+        &SOAR_PROGRAM_ID,
+    )
+    .0
+}
+
+/// `GameRecord` PDA: `[b"game_record", player, game_id_le]`.
+pub fn game_record_address(player: &Pubkey, game_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[GAME_RECORD_SEED, player.as_ref(), &game_id.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+/// Singleton `VerifierRegistry` PDA: `[b"verifier_registry"]`.
+pub fn verifier_registry_address() -> Pubkey {
+    Pubkey::find_program_address(&[VERIFIER_REGISTRY_SEED], &ID).0
+}
+
+/// `AttestedResult` PDA: `[b"attested_result", player, game_id_le]`.
+pub fn attested_result_address(player: &Pubkey, game_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            ATTESTED_RESULT_SEED,
+            player.as_ref(),
+            &game_id.to_le_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// `ActionChain` PDA: `[b"action_chain", player, game_id_le]`.
+pub fn action_chain_address(player: &Pubkey, game_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[ACTION_CHAIN_SEED, player.as_ref(), &game_id.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+/// `AchievementDefinition` PDA: `[b"achievement", achievement_id_le]`.
+pub fn achievement_address(achievement_id: u16) -> Pubkey {
+    Pubkey::find_program_address(&[ACHIEVEMENT_SEED, &achievement_id.to_le_bytes()], &ID).0
+}
+
+/// `AchievementUnlock` PDA: `[b"achievement_unlock", achievement, player]`.
+pub fn achievement_unlock_address(achievement: &Pubkey, player: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            ACHIEVEMENT_UNLOCK_SEED,
+            achievement.as_ref(),
+            player.as_ref(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// The program's VRF identity PDA, which signs randomness requests.
+pub fn program_identity_address() -> Pubkey {
+    Pubkey::find_program_address(&[ephemeral_vrf_sdk::consts::IDENTITY], &ID).0
+}
+
+/// Upgradeable-loader ProgramData account, needed by `initialize_config`.
+pub fn program_data_address() -> Pubkey {
+    get_program_data_address(&ID)
+}
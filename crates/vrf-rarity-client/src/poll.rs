@@ -0,0 +1,76 @@
+//! Fetching and polling `RarityResult` accounts until the oracle fulfills them.
+
+use std::time::{Duration, Instant};
+
+use anchor_lang::prelude::Pubkey;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_rpc_client::rpc_client::RpcClient;
+
+use crate::state::{decode_rarity_result, RarityResult};
+use crate::{Error, Result};
+
+/// Same defaults as `pollForResult` in the TS client.
+pub const DEFAULT_POLL_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Fetch a rarity result. `None` if the account does not exist.
+pub fn fetch_rarity_result(rpc: &RpcClient, address: &Pubkey) -> Result<Option<RarityResult>> {
+    let account = rpc
+        .get_account_with_commitment(address, rpc.commitment())?
+        .value;
+    account
+        .map(|account| decode_rarity_result(&account.data))
+        .transpose()
+}
+
+/// Poll until the VRF callback fulfills the result, or `timeout` elapses.
+pub fn poll_for_fulfillment(
+    rpc: &RpcClient,
+    address: &Pubkey,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<RarityResult> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if let Some(result) = fetch_rarity_result(rpc, address)? {
+            if result.fulfilled {
+                return Ok(result);
+            }
+        }
+        std::thread::sleep(interval);
+    }
+    Err(Error::Timeout)
+}
+
+/// Async variant of [`fetch_rarity_result`].
+pub async fn fetch_rarity_result_async(
+    rpc: &AsyncRpcClient,
+    address: &Pubkey,
+) -> Result<Option<RarityResult>> {
+    let account = rpc
+        .get_account_with_commitment(address, rpc.commitment())
+        .await?
+        .value;
+    account
+        .map(|account| decode_rarity_result(&account.data))
+        .transpose()
+}
+
+/// Async variant of [`poll_for_fulfillment`].
+pub async fn poll_for_fulfillment_async(
+    rpc: &AsyncRpcClient,
+    address: &Pubkey,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<RarityResult> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if let Some(result) = fetch_rarity_result_async(rpc, address).await? {
+            if result.fulfilled {
+                return Ok(result);
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+    Err(Error::Timeout)
+}
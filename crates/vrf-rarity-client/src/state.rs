@@ -0,0 +1,20 @@
+//! Account decoding.
+
+use anchor_lang::AccountDeserialize;
+
+pub use vrf_rarity::{
+    AchievementCondition, AchievementDefinition, AchievementUnlock, ActionChain, AttestedResult,
+    Comparison, GameContext, GameRecord, GameSession, GameSummary, GoldenAgeResult, LootboxResult,
+    LootboxReward, PlayerRollStats, ProbabilityCheck, RarityBatchResult, RarityConfig,
+    RarityResult, RarityRoll, RarityTable, RollLedger, ScoreSubmission, SummaryStat, Tribe,
+    UnitClass, VerifierRegistry, WeightedRollResult, WeightedTable,
+};
+
+/// Decode any vrf-rarity account, checking its discriminator.
+pub fn decode_account<T: AccountDeserialize>(data: &[u8]) -> crate::Result<T> {
+    Ok(T::try_deserialize(&mut &data[..])?)
+}
+
+pub fn decode_rarity_result(data: &[u8]) -> crate::Result<RarityResult> {
+    decode_account(data)
+}
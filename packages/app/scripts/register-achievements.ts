@@ -0,0 +1,68 @@
+/**
+ * One-time achievement registration script for Tribes on Solana devnet.
+ *
+ * Creates an AchievementDefinition in vrf-rarity for each entry of the seed
+ * list in src/magicblock/achievements.ts, so players can claim them with
+ * claim_achievement. Definitions that already exist are skipped, so the
+ * script is safe to re-run after adding an achievement.
+ *
+ * The signer must be the vrf-rarity config authority (SOLANA_KEYPAIR, or
+ * ~/.config/solana/id.json).
+ *
+ * Usage: pnpm achievements:register
+ */
+
+import { Connection, Keypair } from '@solana/web3.js'
+import { AnchorProvider, BN, Program, Wallet } from '@coral-xyz/anchor'
+import { ACHIEVEMENTS, deriveAchievementPDA } from '../src/magicblock/achievements'
+import vrfRarityIdl from '../src/magicblock/vrf_rarity.json'
+import * as fs from 'fs'
+import * as os from 'os'
+import * as path from 'path'
+
+const DEVNET_RPC = 'https://api.devnet.solana.com'
+
+async function main(): Promise<void> {
+  console.log('=== Tribes Achievement Registration (Devnet) ===\n')
+
+  const connection = new Connection(DEVNET_RPC, 'confirmed')
+
+  // Config authority keypair from the Solana CLI config
+  const keypairPath =
+    process.env.SOLANA_KEYPAIR ?? path.join(os.homedir(), '.config/solana/id.json')
+  const raw = JSON.parse(fs.readFileSync(keypairPath, 'utf-8'))
+  const authority = Keypair.fromSecretKey(Uint8Array.from(raw))
+  console.log('Authority:', authority.publicKey.toBase58(), '\n')
+
+  const provider = new AnchorProvider(connection, new Wallet(authority), {
+    commitment: 'confirmed',
+  })
+  // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  const program = new Program(vrfRarityIdl as any, provider)
+
+  for (const def of ACHIEVEMENTS) {
+    const [achievement] = deriveAchievementPDA(def.achievementId)
+    if (await connection.getAccountInfo(achievement)) {
+      console.log(`  ${def.id} (#${def.achievementId}) already registered: ${achievement.toBase58()}`)
+      continue
+    }
+
+    const { stat, comparison, threshold } = def.condition
+    const sig = await program.methods
+      .createAchievement(def.achievementId, def.id, {
+        stat: { [stat]: {} },
+        comparison: { [comparison]: {} },
+        threshold: new BN(threshold),
+      })
+      .accounts({ authority: authority.publicKey })
+      .rpc()
+    console.log(`  ${def.id} (#${def.achievementId}) registered: ${achievement.toBase58()} tx: ${sig}`)
+  }
+
+  console.log('\n=== Achievement Registration Complete ===')
+}
+
+main().catch((err) => {
+  console.error('Registration failed:', err)
+  process.exit(1)
+})
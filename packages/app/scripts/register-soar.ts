@@ -1,8 +1,13 @@
 /**
  * One-time SOAR game registration script for Tribes on Solana devnet.
  *
- * Registers the game, creates a Floor Price leaderboard, and registers achievements.
- * Outputs all addresses to packages/app/src/magicblock/soar-devnet.json.
+ * Registers the game, creates a Floor Price leaderboard, then hands game
+ * authority to vrf-rarity's soar_authority PDA, so scores can only be
+ * submitted through the program's submit_score. Outputs the public addresses
+ * to packages/app/src/magicblock/soar-devnet.json.
+ *
+ * The payer is the Solana CLI keypair (SOLANA_KEYPAIR, or
+ * ~/.config/solana/id.json); it holds no authority once the script finishes.
  *
  * Usage: pnpm soar:register
  */
@@ -15,22 +20,15 @@ import {
 } from '@solana/web3.js'
 import { AnchorProvider, Wallet } from '@coral-xyz/anchor'
 import { SoarProgram } from '@magicblock-labs/soar-sdk'
+import { SOAR_AUTHORITY_SEED, VRF_RARITY_PROGRAM_ID } from '../src/magicblock/config'
 import * as fs from 'fs'
+import * as os from 'os'
 import * as path from 'path'
 import { fileURLToPath } from 'url'
 
 const __filename = fileURLToPath(import.meta.url)
 const __dirname = path.dirname(__filename)
 
-// Achievements to register (must match ACHIEVEMENTS in soar.ts)
-const ACHIEVEMENTS = [
-  { id: 'first_wonder', title: 'First Wonder', description: 'Complete your first wonder' },
-  { id: 'ten_kills', title: '10 Kills', description: 'Kill 10 enemy units in a single game' },
-  { id: 'golden_age', title: 'Golden Age', description: 'Trigger a golden age' },
-  { id: 'tech_leader', title: 'Tech Leader', description: 'Research 10 technologies' },
-  { id: 'empire_builder', title: 'Empire Builder', description: 'Found 5 settlements' },
-]
-
 const DEVNET_RPC = 'https://api.devnet.solana.com'
 const CONFIG_PATH = path.resolve(__dirname, '../src/magicblock/soar-devnet.json')
 
@@ -50,22 +48,15 @@ async function main(): Promise<void> {
 
   const connection = new Connection(DEVNET_RPC, 'confirmed')
 
-  // Load or generate the game authority keypair (persisted for re-runs)
-  const KEYPAIR_PATH = path.resolve(__dirname, '../src/magicblock/soar-authority.json')
-  let authority: Keypair
-  if (fs.existsSync(KEYPAIR_PATH)) {
-    const raw = JSON.parse(fs.readFileSync(KEYPAIR_PATH, 'utf-8'))
-    authority = Keypair.fromSecretKey(Uint8Array.from(raw))
-    console.log('Loaded existing authority:', authority.publicKey.toBase58())
-  } else {
-    authority = Keypair.generate()
-    fs.writeFileSync(KEYPAIR_PATH, JSON.stringify(Array.from(authority.secretKey)))
-    console.log('Generated new authority:', authority.publicKey.toBase58())
-    console.log('  Saved to:', KEYPAIR_PATH)
-  }
+  // Payer keypair from the Solana CLI config; never written into the app
+  const keypairPath =
+    process.env.SOLANA_KEYPAIR ?? path.join(os.homedir(), '.config/solana/id.json')
+  const raw = JSON.parse(fs.readFileSync(keypairPath, 'utf-8'))
+  const payer = Keypair.fromSecretKey(Uint8Array.from(raw))
+  console.log('Payer:', payer.publicKey.toBase58())
 
   // Check balance — need ~0.05 SOL for all registration txs
-  const balance = await connection.getBalance(authority.publicKey)
+  const balance = await connection.getBalance(payer.publicKey)
   const balanceSOL = balance / LAMPORTS_PER_SOL
   console.log(`Balance: ${balanceSOL} SOL`)
 
@@ -75,7 +66,7 @@ async function main(): Promise<void> {
     let airdropSuccess = false
     for (let attempt = 1; attempt <= 3; attempt++) {
       try {
-        const sig = await connection.requestAirdrop(authority.publicKey, LAMPORTS_PER_SOL)
+        const sig = await connection.requestAirdrop(payer.publicKey, LAMPORTS_PER_SOL)
         await connection.confirmTransaction(sig, 'confirmed')
         console.log(`Airdrop confirmed: 1 SOL (attempt ${attempt})\n`)
         airdropSuccess = true
@@ -91,7 +82,7 @@ async function main(): Promise<void> {
     if (!airdropSuccess) {
       console.error('\nAirdrop failed. Fund this address manually:')
       console.error('  https://faucet.solana.com')
-      console.error('  Address:', authority.publicKey.toBase58())
+      console.error('  Address:', payer.publicKey.toBase58())
       console.error('\nThen re-run: pnpm soar:register')
       process.exit(1)
     }
@@ -99,15 +90,22 @@ async function main(): Promise<void> {
     console.log('Sufficient balance, skipping airdrop.\n')
   }
 
-  // Create Anchor provider with the authority wallet
-  const wallet = new Wallet(authority)
+  // Create Anchor provider with the payer wallet
+  const wallet = new Wallet(payer)
   const provider = new AnchorProvider(connection, wallet, {
     commitment: 'confirmed',
   })
 
   const soar = SoarProgram.get(provider)
 
-  // Step 1: Register the game
+  // vrf-rarity's PDA signs submit_score CPIs, so it must be a game authority
+  const [soarAuthority] = PublicKey.findProgramAddressSync(
+    [Buffer.from(SOAR_AUTHORITY_SEED)],
+    VRF_RARITY_PROGRAM_ID,
+  )
+
+  // Step 1: Register the game. The payer is its authority only until step 3,
+  // since adding the leaderboard needs an authority that can sign here.
   console.log('Registering game on SOAR...')
   const newGameKeypair = Keypair.generate()
   const gameResult = await soar.initializeNewGame(
@@ -117,7 +115,7 @@ async function main(): Promise<void> {
     3,                             // Genre.Adventure = 3
     2,                             // GameType.Web = 2
     PublicKey.default,             // nftMeta (placeholder)
-    [authority.publicKey],         // authorities
+    [payer.publicKey],         // authorities (replaced in step 3)
   )
   await soar.sendAndConfirmTransaction(gameResult.transaction, [newGameKeypair])
   const gameAddress = gameResult.newGame.toBase58()
@@ -127,7 +125,7 @@ async function main(): Promise<void> {
   console.log('Creating Floor Price leaderboard...')
   const gameClient = await soar.newGameClient(gameResult.newGame)
   const lbResult = await gameClient.addLeaderBoard(
-    authority.publicKey,
+    payer.publicKey,
     'Floor Price',     // description
     PublicKey.default,  // nftMeta (placeholder)
     20,                 // scoresToRetain (top 20)
@@ -139,28 +137,21 @@ async function main(): Promise<void> {
   console.log('  Leaderboard:', leaderboardAddress)
   console.log('  Top Entries:', topEntriesAddress)
 
-  // Step 3: Register achievements
-  console.log('Registering achievements...')
-  const achievementAddresses: Record<string, string> = {}
-  for (const achievement of ACHIEVEMENTS) {
-    const achResult = await gameClient.addAchievement(
-      authority.publicKey,
-      achievement.title,
-      achievement.description,
-      PublicKey.default, // nftMeta (placeholder)
-    )
-    await soar.sendAndConfirmTransaction(achResult.transaction)
-    achievementAddresses[achievement.id] = achResult.newAchievement.toBase58()
-    console.log(`  ${achievement.title}: ${achResult.newAchievement.toBase58()}`)
-  }
+  // Step 3: Make the program's PDA the only game authority
+  console.log('Handing game authority to', soarAuthority.toBase58())
+  const updateResult = await soar.updateGameAccount(
+    gameResult.newGame,
+    payer.publicKey,
+    undefined,       // keep game metadata
+    [soarAuthority], // new authorities
+  )
+  await soar.sendAndConfirmTransaction(updateResult.transaction)
 
-  // Step 4: Write config
+  // Step 4: Write config (public addresses only)
   const config = {
     gameAddress,
     leaderboardAddress,
     topEntriesAddress,
-    achievementAddresses,
-    authoritySecretKey: Array.from(authority.secretKey),
   }
 
   fs.writeFileSync(CONFIG_PATH, JSON.stringify(config, null, 2) + '\n')
@@ -107,15 +107,15 @@ function GameView(): JSX.Element {
 }
 
 function GameApp(): JSX.Element {
-  const { state, startGame, soarService } = useGameContext()
+  const { state, startGame, soarService, gameId, claimAchievements } = useGameContext()
 
   const handleStartGame = useCallback(
-    (tribe: TribeName) => {
+    async (tribe: TribeName) => {
       // Get AI tribes (exclude selected tribe, and unavailable tribes)
       const availableTribes: TribeName[] = ['monkes', 'geckos', 'degods', 'cets']
       const aiTribes = availableTribes.filter((t) => t !== tribe).slice(0, 3)
 
-      startGame({
+      await startGame({
         seed: Date.now(),
         humanTribe: tribe,
         aiTribes,
@@ -139,7 +139,15 @@ function GameApp(): JSX.Element {
   return (
     <>
       <GameView />
-      {gameOver && <EndGameScreen state={state} onPlayAgain={handlePlayAgain} soarService={soarService} />}
+      {gameOver && (
+        <EndGameScreen
+          state={state}
+          onPlayAgain={handlePlayAgain}
+          soarService={soarService}
+          gameId={gameId}
+          onClaimAchievements={claimAchievements}
+        />
+      )}
     </>
   )
 }
@@ -3,13 +3,16 @@
 import { useState } from 'react'
 import type { GameState, TribeId, TribeName } from '@tribes/game-core'
 import { calculatePolicyFloorPriceBonus } from '@tribes/game-core'
-import type { SOARService } from '../magicblock/soar'
+import { summarizeGame, type GameSummary, type SOARService } from '../magicblock/soar'
 import { LeaderboardPanel } from './LeaderboardPanel'
 
 interface EndGameScreenProps {
   state: GameState
   onPlayAgain: () => void
   soarService: SOARService
+  /** On-chain game id, null for unranked games */
+  gameId: number | null
+  onClaimAchievements: (summary: GameSummary) => Promise<string[]>
 }
 
 // Tribe display info
@@ -136,7 +139,13 @@ const CATEGORY_INFO: Record<string, { label: string; icon: string; color: string
   policies: { label: 'Policies', icon: '📜', color: '#14b8a6' },
 }
 
-export function EndGameScreen({ state, onPlayAgain, soarService }: EndGameScreenProps): JSX.Element {
+export function EndGameScreen({
+  state,
+  onPlayAgain,
+  soarService,
+  gameId,
+  onClaimAchievements,
+}: EndGameScreenProps): JSX.Element {
   const [scoreSubmitted, setScoreSubmitted] = useState(false)
   const [submitting, setSubmitting] = useState(false)
   const [showLeaderboard, setShowLeaderboard] = useState(false)
@@ -448,7 +457,9 @@ export function EndGameScreen({ state, onPlayAgain, soarService }: EndGameScreen
             <button
               onClick={async () => {
                 setSubmitting(true)
-                await soarService.submitScore(humanPlayer.total)
+                const summary = summarizeGame(state, humanPlayer.tribeId)
+                await soarService.submitScore(gameId, summary)
+                await onClaimAchievements(summary)
                 setScoreSubmitted(true)
                 setSubmitting(false)
               }}
@@ -50,7 +50,7 @@ export function GameUI({ hoveredTile, mousePosition, onZoomIn, onZoomOut }: Game
     canSwapPolicies,
     events,
     vrfService,
-    nextVRFNonce,
+    gameId,
     selectUnit,
     selectSettlement,
     addEvent,
@@ -209,7 +209,7 @@ export function GameUI({ hoveredTile, mousePosition, onZoomIn, onZoomOut }: Game
 
   // Handle minting a pending unit — async to support VRF polling
   const handleMintUnit = useCallback(async (): Promise<Unit | null> => {
-    if (!pendingMintInfo) return null
+    if (!pendingMintInfo || !state) return null
     // Capture mint info BEFORE dispatching so popup persists during animation
     setActiveMint({
       pendingMint: pendingMintInfo.mint,
@@ -217,8 +217,12 @@ export function GameUI({ hoveredTile, mousePosition, onZoomIn, onZoomOut }: Game
     })
 
     // Request VRF rarity roll (on-chain or local fallback)
-    const nonce = nextVRFNonce()
-    const { resultPDA } = await vrfService.requestRarityRoll(nonce)
+    const { resultPDA } = await vrfService.requestRarityRoll({
+      gameId,
+      turn: state.turn,
+      settlementId: pendingMintInfo.mint.settlementId,
+      unitType: pendingMintInfo.mint.unitType,
+    })
 
     // Wait for VRF result — LocalVRFService resolves immediately,
     // OnChainVRFService polls until oracle callback fulfills
@@ -249,10 +253,17 @@ export function GameUI({ hoveredTile, mousePosition, onZoomIn, onZoomOut }: Game
         u.position.r === pendingMintInfo.mint.position.r &&
         u.type === pendingMintInfo.mint.unitType
       )
+      // Record the roll as used by this unit; the mint already happened,
+      // so a failed consume only leaves the roll to show up as skipped.
+      if (newUnit) {
+        vrfService.consumeRoll(resultPDA, newUnit.id).catch(err => {
+          console.warn('[VRF] consume_roll failed:', err)
+        })
+      }
       return newUnit ?? null
     }
     return null
-  }, [pendingMintInfo, vrfService, nextVRFNonce, dispatch, state?.currentPlayer])
+  }, [pendingMintInfo, vrfService, gameId, dispatch, state])
 
   // Handle completing a mint animation (Continue button clicked)
   const handleMintComplete = () => {
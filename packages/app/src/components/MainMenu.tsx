@@ -354,7 +354,7 @@ function TribeCard({ tribe, available, selected, onSelect, index }: TribeCardPro
 // ─── Main Menu ────────────────────────────────────────────────────────────────
 
 interface MainMenuProps {
-  onStartGame: (tribe: TribeName) => void
+  onStartGame: (tribe: TribeName) => Promise<void>
   soarService: SOARService
 }
 
@@ -363,6 +363,7 @@ export function MainMenu({ onStartGame, soarService }: MainMenuProps): JSX.Eleme
   const [showLeaderboard, setShowLeaderboard] = useState(false)
   const [titleVisible, setTitleVisible] = useState(false)
   const [startHovered, setStartHovered] = useState(false)
+  const [starting, setStarting] = useState(false)
 
   const tribes = Object.values(TRIBE_DEFINITIONS)
   const playableTribeNames = PLAYABLE_TRIBES.map(t => t.name)
@@ -385,8 +386,11 @@ export function MainMenu({ onStartGame, soarService }: MainMenuProps): JSX.Eleme
     return () => clearTimeout(safety)
   }, [titleVisible])
 
-  const handleStart = () => {
-    if (selectedTribe) onStartGame(selectedTribe)
+  const handleStart = async () => {
+    if (!selectedTribe) return
+    setStarting(true)
+    await onStartGame(selectedTribe)
+    setStarting(false)
   }
 
   return (
@@ -592,7 +596,7 @@ export function MainMenu({ onStartGame, soarService }: MainMenuProps): JSX.Eleme
       >
         <button
           onClick={handleStart}
-          disabled={!selectedTribe}
+          disabled={!selectedTribe || starting}
           onMouseEnter={() => setStartHovered(true)}
           onMouseLeave={() => setStartHovered(false)}
           style={{
@@ -620,7 +624,11 @@ export function MainMenu({ onStartGame, soarService }: MainMenuProps): JSX.Eleme
             transform: startHovered && selectedTribe ? 'translateY(-2px)' : 'translateY(0)',
           }}
         >
-          {selectedTribe ? `Lead the ${TRIBE_DEFINITIONS[selectedTribe]?.displayName ?? ''}` : 'Select a Tribe'}
+          {starting
+            ? 'Rolling the map...'
+            : selectedTribe
+              ? `Lead the ${TRIBE_DEFINITIONS[selectedTribe]?.displayName ?? ''}`
+              : 'Select a Tribe'}
         </button>
       </div>
 
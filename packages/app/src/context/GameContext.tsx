@@ -6,6 +6,7 @@ import {
   useReducer,
   useCallback,
   useRef,
+  useState,
   useEffect,
   useMemo,
   type ReactNode,
@@ -39,13 +40,16 @@ import {
   getMilestoneForLevel,
   getGreatPersonDefinition,
   getEffectDefinition,
+  getCurrentEra,
+  MAP_WIDTH,
+  MAP_HEIGHT,
   type GameConfig,
   type ActionResult,
 } from '@tribes/game-core'
 import type { GameEvent } from '../components/EventLog'
 import { createVRFService, type VRFService } from '../magicblock/vrf'
-import { createSOARService, type SOARService } from '../magicblock/soar'
-import { AchievementTracker } from '../magicblock/achievements'
+import { createSOARService, type GameSummary, type SOARService } from '../magicblock/soar'
+import { AchievementTracker, createAchievementClaimService } from '../magicblock/achievements'
 
 // =============================================================================
 // Types
@@ -101,16 +105,19 @@ export interface GameContextValue {
   // Event log
   events: GameEvent[]
 
-  // VRF service (on-chain when wallet connected, local fallback otherwise)
+  // VRF service (on-chain for ranked games, local fallback otherwise)
   vrfService: VRFService
-  /** Returns an incrementing nonce for VRF rolls */
-  nextVRFNonce: () => number
+  /** On-chain game id rolls and scores are bound to; null for unranked games */
+  gameId: number | null
 
   // SOAR service (on-chain when wallet connected + game registered, local fallback otherwise)
   soarService: SOARService
+  /** Claim on-chain the achievements a finished game's summary meets */
+  claimAchievements: (summary: GameSummary) => Promise<string[]>
 
   // Actions
-  startGame: (config: GameConfig) => void
+  /** Start a game; ranked (on-chain seed) when a wallet is connected */
+  startGame: (config: GameConfig) => Promise<void>
   dispatch: (action: GameAction) => ActionResult
   selectTile: (coord: HexCoord | null) => void
   selectUnit: (unitId: UnitId | null) => void
@@ -673,8 +680,10 @@ export function GameProvider({ children }: { children: ReactNode }): JSX.Element
       return createVRFService(null)
     }
   }, [anchorProvider])
-  const vrfNonceRef = useRef(Date.now())
-  const nextVRFNonce = useCallback(() => vrfNonceRef.current++, [])
+  const [gameId, setGameId] = useState<number | null>(null)
+  // Unranked games have no GameContext to bind rolls to and roll locally
+  const localVrfService = useMemo(() => createVRFService(null), [])
+  const gameVrfService = gameId === null ? localVrfService : vrfService
 
   // SOAR service — uses on-chain SOAR when wallet connected + game registered, local fallback otherwise
   const soarService = useMemo(() => {
@@ -691,8 +700,21 @@ export function GameProvider({ children }: { children: ReactNode }): JSX.Element
   if (!achievementTrackerRef.current) {
     achievementTrackerRef.current = new AchievementTracker()
   }
-  // Keep achievement tracker's SOAR service in sync
-  achievementTrackerRef.current.setSoarService(soarService)
+  // Achievements are claimed through the program when a wallet is connected
+  const achievementClaimService = useMemo(
+    () => createAchievementClaimService(anchorProvider),
+    [anchorProvider]
+  )
+  achievementTrackerRef.current.setClaimService(achievementClaimService)
+  const claimAchievements = useCallback(
+    (summary: GameSummary) => achievementTrackerRef.current?.claimEarned(gameId, summary) ?? Promise.resolve([]),
+    [gameId]
+  )
+
+  // Ranked game whose turns are recorded, and the human's actions so far this
+  // turn; each finished turn's actions are committed to the game's action chain
+  const rankedGameRef = useRef<{ gameId: number; vrfService: VRFService } | null>(null)
+  const turnActionsRef = useRef<GameAction[]>([])
 
   // Keep a ref to the latest game state to avoid stale closure issues
   const latestGameStateRef = useRef<GameState | null>(null)
@@ -700,10 +722,36 @@ export function GameProvider({ children }: { children: ReactNode }): JSX.Element
     latestGameStateRef.current = state.gameState
   }, [state.gameState])
 
-  const startGame = useCallback((config: GameConfig) => {
+  const startGame = useCallback(async (config: GameConfig) => {
     achievementTrackerRef.current?.reset()
-    internalDispatch({ type: 'START_GAME', config })
-  }, [])
+    const id = Date.now()
+
+    // Ranked games take their map seed from the GameSession's VRF seed
+    let sessionSeed: number | null = null
+    try {
+      sessionSeed = await vrfService.startGameSession({
+        gameId: id,
+        tribe: config.humanTribe,
+        mapWidth: MAP_WIDTH,
+        mapHeight: MAP_HEIGHT,
+        playerCount: 1 + config.aiTribes.length,
+      })
+    } catch (err) {
+      console.warn('[VRF] Game session failed:', err)
+    }
+
+    turnActionsRef.current = []
+    if (sessionSeed === null) {
+      console.warn('[VRF] No game session seed, starting unranked with a local seed')
+      rankedGameRef.current = null
+      setGameId(null)
+      internalDispatch({ type: 'START_GAME', config })
+    } else {
+      rankedGameRef.current = { gameId: id, vrfService }
+      setGameId(id)
+      internalDispatch({ type: 'START_GAME', config: { ...config, seed: sessionSeed } })
+    }
+  }, [vrfService])
 
   const dispatch = useCallback(
     (action: GameAction): ActionResult => {
@@ -748,6 +796,18 @@ export function GameProvider({ children }: { children: ReactNode }): JSX.Element
 
       let currentState = result.state
 
+      // Log the human's action; END_TURN records the finished turn and the
+      // era it leaves the player in
+      turnActionsRef.current.push(action)
+      if (action.type === 'END_TURN') {
+        const ranked = rankedGameRef.current
+        const player = currentState.players.find(p => p.tribeId === oldState.currentPlayer)
+        if (ranked && player) {
+          ranked.vrfService.recordTurn(ranked.gameId, oldState.turn, turnActionsRef.current, getCurrentEra(player))
+        }
+        turnActionsRef.current = []
+      }
+
       // Detect lootbox claims (only for human player actions like MOVE_UNIT)
       let lootboxReward: LootboxRewardInfo | null = null
       if (action.type === 'MOVE_UNIT') {
@@ -1039,6 +1099,7 @@ export function GameProvider({ children }: { children: ReactNode }): JSX.Element
     })
 
     if (attackResult.success && attackResult.state) {
+      turnActionsRef.current.push({ type: 'DECLARE_WAR', target: target.owner }, { type: 'ATTACK', attackerId, targetId })
       latestGameStateRef.current = attackResult.state
       internalDispatch({ type: 'SET_STATE', state: attackResult.state })
     }
@@ -1064,9 +1125,10 @@ export function GameProvider({ children }: { children: ReactNode }): JSX.Element
     pendingWarAttack: state.pendingWarAttack,
     canSwapPolicies: state.canSwapPolicies,
     events: state.events,
-    vrfService,
-    nextVRFNonce,
+    vrfService: gameVrfService,
+    gameId,
     soarService,
+    claimAchievements,
     startGame,
     dispatch,
     selectTile,
@@ -1,6 +1,6 @@
 // Hook for wallet-aware VRF service
 
-import { useMemo, useRef } from 'react'
+import { useMemo } from 'react'
 import { useAnchorWallet, useConnection } from '@solana/wallet-adapter-react'
 import { AnchorProvider } from '@coral-xyz/anchor'
 import { createVRFService, type VRFService } from '../magicblock/vrf'
@@ -25,11 +25,3 @@ export function useVRF(): { vrfService: VRFService; isOnChain: boolean } {
 
   return { vrfService, isOnChain }
 }
-
-/**
- * Returns a unique nonce for each VRF request within this session.
- */
-export function useVRFNonce(): () => number {
-  const nonceRef = useRef(0)
-  return () => ++nonceRef.current
-}
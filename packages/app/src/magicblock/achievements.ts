@@ -1,42 +1,162 @@
-// Achievement tracking and SOAR submission
-// Checks game state transitions for achievement conditions
+// Achievement tracking and on-chain claims
+// The seed list below is registered on-chain as AchievementDefinitions
+// (`pnpm achievements:register`); the same conditions drive the in-game
+// checks, so the two cannot drift.
 
+import { PublicKey, Transaction } from '@solana/web3.js'
+import { AnchorProvider, BN, Program } from '@coral-xyz/anchor'
 import type { GameState, TribeId } from '@tribes/game-core'
-import { ACHIEVEMENTS, type SOARService } from './soar'
+import { ACHIEVEMENT_SEED, ACHIEVEMENT_UNLOCK_SEED, VRF_RARITY_PROGRAM_ID } from './config'
+import { finalizeGameInstruction, summarizeGame, type GameSummary } from './soar'
+import vrfRarityIdl from './vrf_rarity.json'
 
 // ---------------------------------------------------------------------------
-// Achievement condition checkers
+// Seed list (mirrors AchievementDefinition)
 // ---------------------------------------------------------------------------
 
-type AchievementChecker = (state: GameState, tribeId: TribeId) => boolean
+/** `stat <comparison> threshold` over a game summary (mirrors AchievementCondition) */
+export interface AchievementCondition {
+  stat: keyof GameSummary
+  comparison: 'atLeast' | 'atMost'
+  threshold: number
+}
 
-const ACHIEVEMENT_CHECKERS: Record<string, AchievementChecker> = {
-  first_wonder: (state, tribeId) => {
-    return state.wonders.some(w => w.builtBy === tribeId)
-  },
+export interface AchievementDef {
+  /** App id, stored on-chain as the definition's slug */
+  id: string
+  /** AchievementDefinition PDA seed */
+  achievementId: number
+  title: string
+  description: string
+  condition: AchievementCondition
+}
 
-  ten_kills: (_state, _tribeId) => {
-    const player = _state.players.find(p => p.tribeId === _tribeId)
-    return (player?.killCount ?? 0) >= 10
+export const ACHIEVEMENTS: AchievementDef[] = [
+  {
+    id: 'first_wonder',
+    achievementId: 0,
+    title: 'First Wonder',
+    description: 'Complete your first wonder',
+    condition: { stat: 'wondersBuilt', comparison: 'atLeast', threshold: 1 },
   },
-
-  golden_age: (_state, _tribeId) => {
-    const player = _state.players.find(p => p.tribeId === _tribeId)
-    return (player?.goldenAge.triggersUsed.length ?? 0) > 0
+  {
+    id: 'ten_kills',
+    achievementId: 1,
+    title: '10 Kills',
+    description: 'Kill 10 enemy units in a single game',
+    condition: { stat: 'killCount', comparison: 'atLeast', threshold: 10 },
   },
-
-  tech_leader: (_state, _tribeId) => {
-    const player = _state.players.find(p => p.tribeId === _tribeId)
-    return (player?.researchedTechs.length ?? 0) >= 10
+  {
+    id: 'golden_age',
+    achievementId: 2,
+    title: 'Golden Age',
+    description: 'Trigger a golden age',
+    condition: { stat: 'goldenAges', comparison: 'atLeast', threshold: 1 },
+  },
+  {
+    id: 'tech_leader',
+    achievementId: 3,
+    title: 'Tech Leader',
+    description: 'Research 10 technologies',
+    condition: { stat: 'techsResearched', comparison: 'atLeast', threshold: 10 },
   },
+  {
+    id: 'empire_builder',
+    achievementId: 4,
+    title: 'Empire Builder',
+    description: 'Found 5 settlements',
+    condition: { stat: 'settlementsOwned', comparison: 'atLeast', threshold: 5 },
+  },
+]
+
+/** Same test as AchievementCondition::is_met */
+export function isConditionMet(condition: AchievementCondition, summary: GameSummary): boolean {
+  const value = summary[condition.stat]
+  return condition.comparison === 'atLeast'
+    ? value >= condition.threshold
+    : value <= condition.threshold
+}
+
+export function deriveAchievementPDA(
+  achievementId: number,
+  programId: PublicKey = VRF_RARITY_PROGRAM_ID,
+): [PublicKey, number] {
+  const idBuf = new BN(achievementId).toArrayLike(Buffer, 'le', 2)
+  return PublicKey.findProgramAddressSync([Buffer.from(ACHIEVEMENT_SEED), idBuf], programId)
+}
+
+// ---------------------------------------------------------------------------
+// Claim service
+// ---------------------------------------------------------------------------
+
+export interface AchievementClaimService {
+  /** Claim achievements earned in a finished game. Returns the ids claimed. */
+  claimAchievements(gameId: number, summary: GameSummary, ids: string[]): Promise<string[]>
+}
+
+export class OnChainAchievementClaimService implements AchievementClaimService {
+  private provider: AnchorProvider
+  // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  private program: Program<any>
 
-  empire_builder: (state, tribeId) => {
-    let count = 0
-    for (const settlement of state.settlements.values()) {
-      if (settlement.owner === tribeId) count++
+  constructor(provider: AnchorProvider) {
+    this.provider = provider
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    this.program = new Program(vrfRarityIdl as any, provider)
+  }
+
+  async claimAchievements(gameId: number, summary: GameSummary, ids: string[]): Promise<string[]> {
+    const player = this.provider.publicKey
+    if (!player) return []
+
+    // One claim per achievement per player; skip ones already unlocked
+    const toClaim: { id: string; achievement: PublicKey }[] = []
+    for (const def of ACHIEVEMENTS) {
+      if (!ids.includes(def.id)) continue
+      const [achievement] = deriveAchievementPDA(def.achievementId)
+      const [unlock] = PublicKey.findProgramAddressSync(
+        [Buffer.from(ACHIEVEMENT_UNLOCK_SEED), achievement.toBuffer(), player.toBuffer()],
+        VRF_RARITY_PROGRAM_ID,
+      )
+      if (!(await this.provider.connection.getAccountInfo(unlock))) {
+        toClaim.push({ id: def.id, achievement })
+      }
     }
-    return count >= 5
-  },
+    if (toClaim.length === 0) return []
+
+    try {
+      // claim_achievement reads the GameRecord, so record the game first if needed
+      const tx = new Transaction()
+      const finalize = await finalizeGameInstruction(this.program, player, gameId, summary)
+      if (finalize) tx.add(finalize)
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      const methods = this.program.methods as any
+      for (const { achievement } of toClaim) {
+        tx.add(await methods.claimAchievement(new BN(gameId)).accounts({ player, achievement }).instruction())
+      }
+      const txSig = await this.provider.sendAndConfirm(tx)
+      console.log('[Achievement] Claimed on-chain:', toClaim.map((c) => c.id).join(', '), 'tx:', txSig)
+      return toClaim.map((c) => c.id)
+    } catch (err) {
+      console.error('[Achievement] Claim failed:', err)
+      return []
+    }
+  }
+}
+
+/** Local fallback (no wallet): achievements stay in-session only */
+export class LocalAchievementClaimService implements AchievementClaimService {
+  async claimAchievements(_gameId: number, _summary: GameSummary, _ids: string[]): Promise<string[]> {
+    return []
+  }
+}
+
+/** Returns an on-chain claim service when a wallet is connected, local fallback otherwise. */
+export function createAchievementClaimService(provider?: AnchorProvider | null): AchievementClaimService {
+  if (provider?.publicKey) {
+    return new OnChainAchievementClaimService(provider)
+  }
+  return new LocalAchievementClaimService()
 }
 
 // ---------------------------------------------------------------------------
@@ -45,22 +165,23 @@ const ACHIEVEMENT_CHECKERS: Record<string, AchievementChecker> = {
 
 export class AchievementTracker {
   private unlocked = new Set<string>()
+  private claimService: AchievementClaimService = new LocalAchievementClaimService()
 
-  /** Set the SOAR service for on-chain achievement submission (currently unused — only minting triggers wallet) */
-  setSoarService(_service: SOARService): void {
-    // No-op: on-chain submission disabled to avoid unwanted wallet popups
+  /** Set the service used to claim achievements on-chain at game end */
+  setClaimService(service: AchievementClaimService): void {
+    this.claimService = service
   }
 
   /** Check all achievements against current state. Returns newly unlocked achievement IDs. */
   checkAchievements(state: GameState, tribeId: TribeId): string[] {
     const newlyUnlocked: string[] = []
+    const summary = summarizeGame(state, tribeId)
 
     for (const achievement of ACHIEVEMENTS) {
       // Skip already-unlocked achievements this session
       if (this.unlocked.has(achievement.id)) continue
 
-      const checker = ACHIEVEMENT_CHECKERS[achievement.id]
-      if (checker && checker(state, tribeId)) {
+      if (isConditionMet(achievement.condition, summary)) {
         this.unlocked.add(achievement.id)
         newlyUnlocked.push(achievement.id)
 
@@ -71,6 +192,20 @@ export class AchievementTracker {
     return newlyUnlocked
   }
 
+  /**
+   * Claim on-chain the achievements the final summary still meets; the
+   * program checks them against the recorded game. Unranked games
+   * (`gameId` null) claim nothing.
+   */
+  async claimEarned(gameId: number | null, summary: GameSummary): Promise<string[]> {
+    if (gameId === null) return []
+    const earned = ACHIEVEMENTS
+      .filter((a) => isConditionMet(a.condition, summary))
+      .map((a) => a.id)
+    if (earned.length === 0) return []
+    return this.claimService.claimAchievements(gameId, summary, earned)
+  }
+
   /** Get all unlocked achievement IDs this session */
   getUnlocked(): string[] {
     return Array.from(this.unlocked)
@@ -36,6 +36,26 @@ export const DEVNET_RPC = 'https://api.devnet.solana.com';
 // ---------------------------------------------------------------------------
 
 export const RARITY_SEED = 'rarity';
+export const CONFIG_SEED = 'config';
+export const RARITY_BATCH_SEED = 'rarity_batch';
+export const PLAYER_STATS_SEED = 'player_stats';
+export const GAME_SESSION_SEED = 'game_session';
+export const GAME_CONTEXT_SEED = 'game_context';
+export const ROLL_LEDGER_SEED = 'roll_ledger';
+export const LOOTBOX_SEED = 'lootbox';
+export const GOLDEN_AGE_SEED = 'golden_age';
+export const PROBABILITY_CHECK_SEED = 'probability_check';
+export const COLLECTIBLE_SEED = 'collectible';
+export const WEIGHTED_TABLE_SEED = 'weighted_table';
+export const WEIGHTED_ROLL_SEED = 'weighted_roll';
+export const SOAR_AUTHORITY_SEED = 'soar_authority';
+export const SCORE_SUBMISSION_SEED = 'score_submission';
+export const GAME_RECORD_SEED = 'game_record';
+export const VERIFIER_REGISTRY_SEED = 'verifier_registry';
+export const ATTESTED_RESULT_SEED = 'attested_result';
+export const ACTION_CHAIN_SEED = 'action_chain';
+export const ACHIEVEMENT_SEED = 'achievement';
+export const ACHIEVEMENT_UNLOCK_SEED = 'achievement_unlock';
 
 // ---------------------------------------------------------------------------
 // Rarity helpers (mirrors on-chain logic)
@@ -54,21 +74,71 @@ export function rarityFromIndex(index: number): UnitRarity {
   return RARITY_INDEX[index] ?? 'common';
 }
 
-/** Convert a raw roll value [0,99] to UnitRarity (same thresholds as on-chain) */
-export function rarityFromRoll(roll: number): UnitRarity {
-  if (roll <= 49) return 'common';
-  if (roll <= 79) return 'uncommon';
-  if (roll <= 94) return 'rare';
-  if (roll <= 98) return 'epic';
-  return 'legendary';
-}
+/** Rolls are uniform in [0, ROLL_DENOMINATOR) basis points */
+export const ROLL_DENOMINATOR = 10_000;
 
 // ---------------------------------------------------------------------------
 // Account layout sizes (for rent calculation / deserialization)
 // ---------------------------------------------------------------------------
 
-/** RarityResult account: 8 discriminator + 32 + 8 + 1 + 1 + 1 + 1 = 52 bytes */
-export const RARITY_RESULT_SIZE = 52;
+/**
+ * RarityResult account (layout v2): 8 discriminator + 1 version + 32 player
+ * + 8 nonce + 3 × u8 + 2 roll value + 4 config version + 1 unit class
+ * + 32 randomness + 8 requested slot + 8 fulfilled slot + 8 timestamp
+ * + 1 pity flag + 8 game id + 2 turn + 4 settlement id + 1 unit type
+ * + 1 consumed flag + 4 consumed unit id = 136 bytes
+ */
+export const RARITY_RESULT_SIZE = 136;
+
+/** Layout version stored in RarityResult.version (mirrors RARITY_RESULT_VERSION) */
+export const RARITY_RESULT_VERSION = 2;
+
+/** Size of a baseline (v1) RarityResult, which predates the version byte (mirrors LEGACY_RARITY_RESULT_LEN) */
+export const LEGACY_RARITY_RESULT_SIZE = 52;
+
+/** Slots an unfulfilled roll or batch must wait before cancel_stale_roll / cancel_stale_batch is allowed (mirrors STALE_ROLL_SLOTS) */
+export const STALE_ROLL_SLOTS = 300;
 
 /** VRF request cost on Solana L1 (~0.0005 SOL) */
 export const VRF_REQUEST_COST_LAMPORTS = 500_000;
+
+// ---------------------------------------------------------------------------
+// Program error codes (mirrors VrfRarityError, Anchor custom codes start at 6000)
+// ---------------------------------------------------------------------------
+
+export const VrfRarityErrorCode = {
+  RollNotFulfilled: 6000,
+  Unauthorized: 6001,
+  InvalidRarityTable: 6002,
+  InvalidBatchCount: 6003,
+  AlreadyFulfilled: 6004,
+  PlayerMismatch: 6005,
+  InvalidRarityIndex: 6006,
+  InvalidNonce: 6007,
+  RollNotStale: 6008,
+  InvalidGameSettings: 6009,
+  GameAlreadyStarted: 6010,
+  InvalidTurn: 6011,
+  RollAlreadyConsumed: 6012,
+  InvalidBatchSlot: 6013,
+  InvalidEra: 6014,
+  InvalidProbability: 6015,
+  RarityTooLow: 6016,
+  InvalidWeightedTable: 6017,
+  InvalidScore: 6018,
+  InvalidGameSummary: 6019,
+  TooManyVerifiers: 6020,
+  InvalidAttestation: 6021,
+  VerifierNotAllowed: 6022,
+  InvalidAchievement: 6023,
+  AchievementInactive: 6024,
+  AchievementNotEarned: 6025,
+  NotLegacyResult: 6026,
+  WeightedTableChanged: 6027,
+  RarityConfigChanged: 6028,
+  TurnNotCommitted: 6029,
+  InvalidLootbox: 6030,
+  ReservedWeightedTable: 6031,
+} as const;
+
+export type VrfRarityErrorName = keyof typeof VrfRarityErrorCode;
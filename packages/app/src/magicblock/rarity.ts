@@ -0,0 +1,16 @@
+import type { UnitRarity } from '@tribes/game-core';
+import { rarityFromIndex } from './config';
+
+// crates/rarity-math compiled by `pnpm build:wasm`, so local rolls map to
+// tiers with the same code as the program.
+import initRarityMath, { tierForRoll } from '../wasm/rarity-math/rarity_math';
+
+let ready: Promise<unknown> | null = null;
+
+/** Convert a raw roll value [0,9999] bps to UnitRarity using the launch table */
+export async function rarityFromRoll(roll: number): Promise<UnitRarity> {
+  ready ??= initRarityMath();
+  await ready;
+  // An empty table selects DEFAULT_RARITY_THRESHOLDS
+  return rarityFromIndex(tierForRoll(new Uint16Array(0), roll));
+}
@@ -1,9 +1,19 @@
-// SOAR leaderboard and achievement integration
-// Uses @magicblock-labs/soar-sdk for on-chain rankings
+// SOAR leaderboard integration
+// Scores are submitted through vrf-rarity's submit_score, whose PDA is the
+// only SOAR game authority; @magicblock-labs/soar-sdk handles player
+// registration and leaderboard reads.
 
-import { Keypair, PublicKey } from '@solana/web3.js'
-import { AnchorProvider, BN } from '@coral-xyz/anchor'
+import { PublicKey, type TransactionInstruction } from '@solana/web3.js'
+import { AnchorProvider, BN, Program } from '@coral-xyz/anchor'
 import { SoarProgram } from '@magicblock-labs/soar-sdk'
+import {
+  calculateFloorPrice,
+  MAX_TURNS,
+  type GameState,
+  type TribeId,
+} from '@tribes/game-core'
+import { GAME_RECORD_SEED, VRF_RARITY_PROGRAM_ID } from './config'
+import vrfRarityIdl from './vrf_rarity.json'
 
 // Devnet config — populated by `pnpm soar:register`
 import soarConfig from './soar-devnet.json'
@@ -19,35 +29,74 @@ export interface LeaderboardEntry {
   score: number
 }
 
-export interface AchievementDef {
-  id: string
-  title: string
-  description: string
+/** End-of-game stats for the human player (mirrors GameSummary) */
+export interface GameSummary {
+  turnsPlayed: number
+  floorPrice: number
+  killCount: number
+  wondersBuilt: number
+  techsResearched: number
+  settlementsOwned: number
+  goldenAges: number
+  greatPeople: number
 }
 
-// ---------------------------------------------------------------------------
-// Predefined achievements
-// ---------------------------------------------------------------------------
+/** Summarize a finished game for finalize_game */
+export function summarizeGame(state: GameState, tribeId: TribeId): GameSummary {
+  const player = state.players.find((p) => p.tribeId === tribeId)
+  let settlementsOwned = 0
+  for (const settlement of state.settlements.values()) {
+    if (settlement.owner === tribeId) settlementsOwned++
+  }
+  return {
+    turnsPlayed: Math.min(state.turn, MAX_TURNS),
+    floorPrice: calculateFloorPrice(state, tribeId),
+    killCount: player?.killCount ?? 0,
+    wondersBuilt: state.wonders.filter((w) => w.builtBy === tribeId).length,
+    techsResearched: player?.researchedTechs.length ?? 0,
+    settlementsOwned,
+    goldenAges: player?.goldenAge.triggersUsed.length ?? 0,
+    greatPeople: player?.greatPeople.earned.length ?? 0,
+  }
+}
 
-export const ACHIEVEMENTS: AchievementDef[] = [
-  { id: 'first_wonder', title: 'First Wonder', description: 'Complete your first wonder' },
-  { id: 'ten_kills', title: '10 Kills', description: 'Kill 10 enemy units in a single game' },
-  { id: 'golden_age', title: 'Golden Age', description: 'Trigger a golden age' },
-  { id: 'tech_leader', title: 'Tech Leader', description: 'Research 10 technologies' },
-  { id: 'empire_builder', title: 'Empire Builder', description: 'Found 5 settlements' },
-]
+/**
+ * finalize_game for `gameId`, or null if its GameRecord already exists.
+ * submit_score and claim_achievement both read the record.
+ */
+export async function finalizeGameInstruction(
+  // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  program: Program<any>,
+  player: PublicKey,
+  gameId: number,
+  summary: GameSummary,
+): Promise<TransactionInstruction | null> {
+  const id = new BN(gameId)
+  const [gameRecord] = PublicKey.findProgramAddressSync(
+    [Buffer.from(GAME_RECORD_SEED), player.toBuffer(), id.toArrayLike(Buffer, 'le', 8)],
+    VRF_RARITY_PROGRAM_ID,
+  )
+  if (await program.provider.connection.getAccountInfo(gameRecord)) return null
+
+  // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  return (program.methods as any)
+    .finalizeGame(id, { ...summary, floorPrice: new BN(summary.floorPrice) })
+    .accounts({ player })
+    .instruction()
+}
 
 // ---------------------------------------------------------------------------
 // SOAR Service interface
 // ---------------------------------------------------------------------------
 
 export interface SOARService {
-  /** Submit a Floor Price score to the leaderboard */
-  submitScore(floorPrice: number): Promise<string | null>
+  /**
+   * Record a finished game and submit its Floor Price to the leaderboard.
+   * `gameId` is null for unranked games, which only score locally.
+   */
+  submitScore(gameId: number | null, summary: GameSummary): Promise<string | null>
   /** Fetch the global leaderboard */
   getLeaderboard(limit?: number): Promise<LeaderboardEntry[]>
-  /** Unlock an achievement on-chain */
-  unlockAchievement(achievementId: string): Promise<string | null>
   /** Whether SOAR is available (wallet connected + game registered) */
   isAvailable(): boolean
 }
@@ -59,14 +108,18 @@ export interface SOARService {
 export class OnChainSOARService implements SOARService {
   private provider: AnchorProvider
   private soar: SoarProgram
-  private authority: Keypair
+  // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  private program: Program<any>
+  private gameAddress: PublicKey
   private leaderboardAddress: PublicKey
   private topEntriesAddress: PublicKey
 
   constructor(provider: AnchorProvider) {
     this.provider = provider
     this.soar = SoarProgram.get(provider)
-    this.authority = Keypair.fromSecretKey(Uint8Array.from(soarConfig.authoritySecretKey))
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    this.program = new Program(vrfRarityIdl as any, provider)
+    this.gameAddress = new PublicKey(soarConfig.gameAddress)
     this.leaderboardAddress = new PublicKey(soarConfig.leaderboardAddress)
     this.topEntriesAddress = new PublicKey(soarConfig.topEntriesAddress)
   }
@@ -75,8 +128,8 @@ export class OnChainSOARService implements SOARService {
     return !!soarConfig.gameAddress && !!this.provider.publicKey
   }
 
-  async submitScore(floorPrice: number): Promise<string | null> {
-    if (!this.isAvailable()) return null
+  async submitScore(gameId: number | null, summary: GameSummary): Promise<string | null> {
+    if (!this.isAvailable() || gameId === null) return null
     const wallet = this.provider.publicKey!
 
     try {
@@ -96,18 +149,23 @@ export class OnChainSOARService implements SOARService {
         }
       }
 
-      // Step 2: Submit score with authority co-signer
-      const scoreResult = await this.soar.submitScoreToLeaderBoard(
-        wallet,
-        this.authority.publicKey,
-        this.leaderboardAddress,
-        new BN(floorPrice),
-      )
-      const txSig = await this.soar.sendAndConfirmTransaction(
-        scoreResult.transaction,
-        [this.authority],
-      )
-      console.log('[SOAR] Score submitted:', floorPrice, 'tx:', txSig)
+      // Step 2: Record the game (once) and submit its score. The program
+      // validates the summary, reads the score from the GameRecord and signs
+      // the SOAR CPI with its soar_authority PDA. Anchor resolves the
+      // program PDAs and the player's SOAR profile and score list.
+      const finalize = await finalizeGameInstruction(this.program, wallet, gameId, summary)
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      const txSig: string = await (this.program.methods as any)
+        .submitScore(new BN(gameId))
+        .accounts({
+          player: wallet,
+          soarGame: this.gameAddress,
+          soarLeaderboard: this.leaderboardAddress,
+          soarTopEntries: this.topEntriesAddress,
+        })
+        .preInstructions(finalize ? [finalize] : [])
+        .rpc()
+      console.log('[SOAR] Score submitted:', summary.floorPrice, 'tx:', txSig)
       return txSig
     } catch (err) {
       console.error('[SOAR] Score submission failed:', err)
@@ -140,35 +198,6 @@ export class OnChainSOARService implements SOARService {
       return []
     }
   }
-
-  async unlockAchievement(achievementId: string): Promise<string | null> {
-    if (!this.isAvailable()) return null
-    const wallet = this.provider.publicKey!
-
-    const achievementAddr = (soarConfig.achievementAddresses as Record<string, string>)[achievementId]
-    if (!achievementAddr) {
-      console.warn('[SOAR] Unknown achievement:', achievementId)
-      return null
-    }
-
-    try {
-      const result = await this.soar.unlockPlayerAchievement(
-        wallet,
-        this.authority.publicKey,
-        new PublicKey(achievementAddr),
-        this.leaderboardAddress,
-      )
-      const txSig = await this.soar.sendAndConfirmTransaction(
-        result.transaction,
-        [this.authority],
-      )
-      console.log('[SOAR] Achievement unlocked:', achievementId, 'tx:', txSig)
-      return txSig
-    } catch (err) {
-      console.error('[SOAR] Achievement unlock failed:', achievementId, err)
-      return null
-    }
-  }
 }
 
 // ---------------------------------------------------------------------------
@@ -184,18 +213,16 @@ interface LocalScore {
 }
 
 export class LocalSOARService implements SOARService {
-  private results = new Map<string, boolean>()
-
   isAvailable(): boolean {
     return true // Always available as local fallback
   }
 
-  async submitScore(floorPrice: number): Promise<string | null> {
+  async submitScore(_gameId: number | null, summary: GameSummary): Promise<string | null> {
     try {
       const scores = this.loadScores()
       scores.push({
         player: 'You',
-        score: floorPrice,
+        score: summary.floorPrice,
         timestamp: Date.now(),
       })
       // Keep top 20, sorted descending
@@ -218,12 +245,6 @@ export class LocalSOARService implements SOARService {
     }))
   }
 
-  async unlockAchievement(achievementId: string): Promise<string | null> {
-    this.results.set(achievementId, true)
-    console.log('[SOAR-Local] Achievement unlocked:', achievementId)
-    return null
-  }
-
   private loadScores(): LocalScore[] {
     try {
       const raw = localStorage.getItem(LOCAL_STORAGE_KEY)
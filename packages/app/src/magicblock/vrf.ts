@@ -1,12 +1,25 @@
-import { PublicKey } from '@solana/web3.js';
+import { PublicKey, Transaction, type TransactionInstruction } from '@solana/web3.js';
 import { AnchorProvider, BN, Program } from '@coral-xyz/anchor';
-import type { UnitRarity } from '@tribes/game-core';
+import {
+  UNIT_DEFINITIONS,
+  type GameAction,
+  type TribeName,
+  type UnitRarity,
+  type UnitType,
+} from '@tribes/game-core';
 import {
   VRF_RARITY_PROGRAM_ID,
   RARITY_SEED,
+  GAME_CONTEXT_SEED,
+  GAME_SESSION_SEED,
+  ROLL_LEDGER_SEED,
+  ACTION_CHAIN_SEED,
+  LEGACY_RARITY_RESULT_SIZE,
+  RARITY_RESULT_VERSION,
   rarityFromIndex,
-  rarityFromRoll,
+  ROLL_DENOMINATOR,
 } from './config';
+import { rarityFromRoll } from './rarity';
 
 // Anchor IDL — provides correct discriminators, account resolution, and
 // instruction serialization so we don't have to construct raw instructions.
@@ -24,15 +37,101 @@ export interface RarityRollResult {
   fulfilled: boolean;
 }
 
+/** Unit class a roll is made for; each class has its own odds on-chain (mirrors UnitClass) */
+export type UnitClass = 'melee' | 'ranged' | 'cavalry' | 'siege' | 'unique';
+
+/** What a rarity roll is for. The program binds the result to these. */
+export interface RarityRollRequest {
+  /** Game the roll belongs to (GameContext game id); null for unranked games */
+  gameId: number | null;
+  /** Current game turn; the context is advanced to it if behind, committing each recorded turn on the way */
+  turn: number;
+  /** Settlement minting the unit */
+  settlementId: string;
+  unitType: UnitType;
+}
+
+/** Settings a ranked game is started with (mirrors start_game) */
+export interface GameSessionRequest {
+  gameId: number;
+  tribe: TribeName;
+  mapWidth: number;
+  mapHeight: number;
+  /** Tribes in the game, including AI */
+  playerCount: number;
+}
+
 export interface VRFService {
+  /**
+   * Start a ranked game: request the VRF map seed and open the game context
+   * rolls are bound to. Resolves with the GameState seed, or null when the
+   * game has no on-chain session and keeps its local seed.
+   */
+  startGameSession(request: GameSessionRequest): Promise<number | null>;
+
   /** Request a VRF-backed rarity roll. Returns tx signature and the PDA to poll. */
-  requestRarityRoll(nonce: number): Promise<{
+  requestRarityRoll(request: RarityRollRequest): Promise<{
     txSignature: string;
     resultPDA: PublicKey;
   }>;
 
-  /** Fetch the current state of a rarity result PDA. Null if not yet fulfilled. */
+  /** Fetch the current state of a rarity result PDA. Null if missing or not yet fulfilled. */
   getRarityResult(resultPDA: PublicKey): Promise<RarityRollResult | null>;
+
+  /** Mark a fulfilled roll as applied to the unit it minted (consume_roll). */
+  consumeRoll(resultPDA: PublicKey, unitId: string): Promise<void>;
+
+  /**
+   * Record the actions a ranked game's player took on a finished turn, and
+   * the player's tech era once it ended. The program only advances a game
+   * context past turns committed to its action chain, so recorded turns are
+   * committed before the next roll; the era is reported with the advance.
+   */
+  recordTurn(gameId: number, turn: number, actions: GameAction[], era: number): void;
+}
+
+/** sha256 of a turn's actions, as committed with commit_turn */
+export async function hashTurnActions(actions: GameAction[]): Promise<Uint8Array> {
+  const data = new TextEncoder().encode(JSON.stringify(actions));
+  return new Uint8Array(await crypto.subtle.digest('SHA-256', data));
+}
+
+// ---------------------------------------------------------------------------
+// Roll metadata (mirrors the RarityResult fields a roll is bound to)
+// ---------------------------------------------------------------------------
+
+/** Class whose rarity table a unit type rolls against */
+export function unitClassFor(unitType: UnitType): UnitClass {
+  switch (unitType) {
+    case 'archer':
+    case 'sniper':
+    case 'rockeeter':
+      return 'ranged';
+    case 'horseman':
+    case 'knight':
+    case 'tank':
+      return 'cavalry';
+    case 'social_engineer':
+    case 'bombard':
+      return 'siege';
+    case 'banana_slinger':
+    case 'neon_geck':
+    case 'deadgod':
+    case 'stuckers':
+      return 'unique';
+    default:
+      return 'melee';
+  }
+}
+
+/** Index of a UnitType in game-core's UNIT_DEFINITIONS (the on-chain unit_type) */
+export function unitTypeIndex(unitType: UnitType): number {
+  return Object.keys(UNIT_DEFINITIONS).indexOf(unitType);
+}
+
+/** Numeric suffix of a game-core id such as `settlement_1a` (ids are base 36) */
+export function idSuffix(id: string): number {
+  return parseInt(id.slice(id.lastIndexOf('_') + 1), 36);
 }
 
 // ---------------------------------------------------------------------------
@@ -51,14 +150,70 @@ export function deriveRarityResultPDA(
   );
 }
 
+export function deriveGameSessionPDA(
+  player: PublicKey,
+  gameId: number,
+  programId: PublicKey = VRF_RARITY_PROGRAM_ID,
+): [PublicKey, number] {
+  const gameIdBuf = new BN(gameId).toArrayLike(Buffer, 'le', 8);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(GAME_SESSION_SEED), player.toBuffer(), gameIdBuf],
+    programId,
+  );
+}
+
+/** GameState seed of a fulfilled session: first 4 bytes of the VRF seed, little-endian */
+export function seedFromSession(seed: number[] | Uint8Array): number {
+  return Buffer.from(seed).readUInt32LE(0);
+}
+
+export function deriveGameContextPDA(
+  player: PublicKey,
+  gameId: number,
+  programId: PublicKey = VRF_RARITY_PROGRAM_ID,
+): [PublicKey, number] {
+  const gameIdBuf = new BN(gameId).toArrayLike(Buffer, 'le', 8);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(GAME_CONTEXT_SEED), player.toBuffer(), gameIdBuf],
+    programId,
+  );
+}
+
+export function deriveActionChainPDA(
+  player: PublicKey,
+  gameId: number,
+  programId: PublicKey = VRF_RARITY_PROGRAM_ID,
+): [PublicKey, number] {
+  const gameIdBuf = new BN(gameId).toArrayLike(Buffer, 'le', 8);
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(ACTION_CHAIN_SEED), player.toBuffer(), gameIdBuf],
+    programId,
+  );
+}
+
+export function deriveRollLedgerPDA(
+  player: PublicKey,
+  programId: PublicKey = VRF_RARITY_PROGRAM_ID,
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(ROLL_LEDGER_SEED), player.toBuffer()],
+    programId,
+  );
+}
+
 // ---------------------------------------------------------------------------
 // On-chain VRF service (Anchor Program-based)
 // ---------------------------------------------------------------------------
 
+/** commit_turn + advance_game_turn pairs sent per catch-up transaction */
+const TURNS_PER_TX = 4;
+
 export class OnChainVRFService implements VRFService {
   private provider: AnchorProvider;
   // eslint-disable-next-line @typescript-eslint/no-explicit-any
   private program: Program<any>;
+  /** Finished turns not yet committed, by game id then turn */
+  private pendingTurns = new Map<number, Map<number, { actionHash: Promise<Uint8Array>; era: number }>>();
 
   constructor(provider: AnchorProvider) {
     this.provider = provider;
@@ -68,45 +223,195 @@ export class OnChainVRFService implements VRFService {
     this.program = new Program(vrfRarityIdl as any, provider);
   }
 
-  async requestRarityRoll(nonce: number): Promise<{
+  async startGameSession(
+    request: GameSessionRequest,
+    timeoutMs = 30_000,
+    intervalMs = 2_000,
+  ): Promise<number> {
+    const payer = this.provider.publicKey;
+    if (!payer) throw new Error('Wallet not connected');
+
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const methods = this.program.methods as any;
+    const gameId = new BN(request.gameId);
+
+    // Anchor resolves the session PDA from [b"game_session", payer, game_id]
+    // and the VRF accounts from the IDL, as for rollRarity.
+    const openContext = await methods
+      .openGameContext(gameId)
+      .accounts({ player: payer })
+      .instruction();
+    await methods
+      .startGame(gameId, { [request.tribe]: {} }, request.mapWidth, request.mapHeight, request.playerCount)
+      .accounts({ payer })
+      .postInstructions([openContext])
+      .rpc();
+
+    const [sessionPDA] = deriveGameSessionPDA(payer, request.gameId);
+    const deadline = Date.now() + timeoutMs;
+    while (Date.now() < deadline) {
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      const session = await (this.program.account as any).gameSession.fetchNullable(sessionPDA);
+      if (session?.fulfilled) return seedFromSession(session.seed);
+      await new Promise((r) => setTimeout(r, intervalMs));
+    }
+    throw new Error('VRF game seed not fulfilled within timeout');
+  }
+
+  async requestRarityRoll(request: RarityRollRequest): Promise<{
     txSignature: string;
     resultPDA: PublicKey;
   }> {
     const payer = this.provider.publicKey;
     if (!payer) throw new Error('Wallet not connected');
+    if (request.gameId === null) throw new Error('Unranked games have no game context to roll in');
+
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const methods = this.program.methods as any;
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const accounts = this.program.account as any;
 
+    // Rolls are only accepted for the context's current turn: open the
+    // context on first use and bring it up to the game's turn.
+    const [gameContext] = deriveGameContextPDA(payer, request.gameId);
+    const context = await accounts.gameContext.fetchNullable(gameContext);
+    const catchUp: TransactionInstruction[] = [];
+    if (!context) {
+      catchUp.push(
+        await methods.openGameContext(new BN(request.gameId)).accounts({ player: payer }).instruction(),
+      );
+    }
+    const currentTurn = context ? context.currentTurn : 1;
+    if (request.turn < currentTurn) {
+      throw new Error(`Game context is already at turn ${currentTurn}`);
+    }
+    catchUp.push(...(await this.turnCatchUp(payer, request.gameId, currentTurn, request.turn)));
+
+    // Long catch-ups go out in their own transactions; the last chunk rides
+    // along with the roll.
+    const chunk = TURNS_PER_TX * 2 + 1;
+    while (catchUp.length > chunk) {
+      await this.provider.sendAndConfirm(new Transaction().add(...catchUp.splice(0, chunk)));
+    }
+    const preInstructions = catchUp;
+
+    // The ledger hands out nonces in order; a new player starts at 0
+    const ledger = await accounts.rollLedger.fetchNullable(deriveRollLedgerPDA(payer)[0]);
+    const nonce: number = ledger ? ledger.nextNonce.toNumber() : 0;
     const [resultPDA] = deriveRarityResultPDA(payer, nonce);
 
-    // Anchor auto-resolves all accounts from the IDL:
-    //   rarityResult  — PDA derived from seeds [b"rarity", payer, nonce]
-    //   oracleQueue   — fixed address in IDL
-    //   programIdentity — PDA derived from seeds [b"identity"]
-    //   vrfProgram, slotHashes, systemProgram — fixed addresses in IDL
-    // eslint-disable-next-line @typescript-eslint/no-explicit-any
-    const txSignature: string = await (this.program.methods as any)
-      .rollRarity(new BN(nonce))
-      .accounts({ payer })
+    // Anchor resolves the remaining accounts from the IDL: the rarity
+    // result, config, player stats and ledger PDAs from payer and nonce,
+    // and the oracle queue, program identity, VRF program and sysvars.
+    // The game context seed reads game_id from the account, so it is
+    // passed explicitly.
+    const txSignature: string = await methods
+      .rollRarity(
+        new BN(nonce),
+        { [unitClassFor(request.unitType)]: {} },
+        request.turn,
+        idSuffix(request.settlementId),
+        unitTypeIndex(request.unitType),
+      )
+      .accounts({ payer, gameContext })
+      .preInstructions(preInstructions)
       .rpc();
 
+    const pending = this.pendingTurns.get(request.gameId);
+    for (let turn = currentTurn; turn < request.turn; turn++) pending?.delete(turn);
+
     return { txSignature, resultPDA };
   }
 
+  recordTurn(gameId: number, turn: number, actions: GameAction[], era: number): void {
+    let pending = this.pendingTurns.get(gameId);
+    if (!pending) {
+      pending = new Map();
+      this.pendingTurns.set(gameId, pending);
+    }
+    pending.set(turn, { actionHash: hashTurnActions(actions), era });
+  }
+
+  /**
+   * commit_turn + advance_game_turn for each turn from `fromTurn` up to
+   * `toTurn`, advancing in the era recorded for the turn left. Turns already
+   * in the action chain are only advanced past.
+   */
+  private async turnCatchUp(
+    payer: PublicKey,
+    gameId: number,
+    fromTurn: number,
+    toTurn: number,
+  ): Promise<TransactionInstruction[]> {
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const methods = this.program.methods as any;
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const accounts = this.program.account as any;
+    const [gameContext] = deriveGameContextPDA(payer, gameId);
+    const [actionChain] = deriveActionChainPDA(payer, gameId);
+    const chain = await accounts.actionChain.fetchNullable(actionChain);
+    const lastCommitted: number = chain ? chain.lastTurn : 0;
+    const pending = this.pendingTurns.get(gameId);
+
+    const instructions: TransactionInstruction[] = [];
+    for (let turn = fromTurn; turn < toTurn; turn++) {
+      const recorded = pending?.get(turn);
+      if (!recorded) throw new Error(`Turn ${turn} has not been recorded`);
+      if (turn > lastCommitted) {
+        const actionHash = await recorded.actionHash;
+        instructions.push(
+          await methods
+            .commitTurn(new BN(gameId), turn, Array.from(actionHash))
+            .accounts({ player: payer })
+            .instruction(),
+        );
+      }
+      instructions.push(
+        await methods
+          .advanceGameTurn(turn + 1, recorded.era)
+          .accounts({ player: payer, gameContext, actionChain })
+          .instruction(),
+      );
+    }
+    return instructions;
+  }
+
   async getRarityResult(resultPDA: PublicKey): Promise<RarityRollResult | null> {
-    try {
-      // eslint-disable-next-line @typescript-eslint/no-explicit-any
-      const result = await (this.program.account as any).rarityResult.fetchNullable(resultPDA);
-      if (!result || !result.fulfilled) return null;
-
-      return {
-        player: result.player,
-        nonce: BN.isBN(result.nonce) ? result.nonce.toNumber() : Number(result.nonce),
-        rarity: rarityFromIndex(result.rarity),
-        rollValue: result.rollValue,
-        fulfilled: result.fulfilled,
-      };
-    } catch {
-      return null;
+    const info = await this.provider.connection.getAccountInfo(resultPDA);
+    if (!info) return null;
+    // Baseline results predate the version byte and cannot be decoded as v2;
+    // they can only be closed with close_legacy_rarity_result.
+    if (info.data.length === LEGACY_RARITY_RESULT_SIZE) {
+      throw new Error(`Rarity result ${resultPDA.toBase58()} uses the legacy v1 layout`);
+    }
+
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const result = (this.program.coder.accounts as any).decode('rarityResult', info.data);
+    if (result.version !== RARITY_RESULT_VERSION) {
+      throw new Error(`Unsupported rarity result version ${result.version}`);
     }
+    if (!result.fulfilled) return null;
+
+    return {
+      player: result.player,
+      nonce: BN.isBN(result.nonce) ? result.nonce.toNumber() : Number(result.nonce),
+      rarity: rarityFromIndex(result.rarity),
+      rollValue: result.rollValue,
+      fulfilled: result.fulfilled,
+    };
+  }
+
+  async consumeRoll(resultPDA: PublicKey, unitId: string): Promise<void> {
+    const player = this.provider.publicKey;
+    if (!player) throw new Error('Wallet not connected');
+
+    // The ledger PDA is resolved from the player; the result's seeds read
+    // its nonce from the account, so it is passed explicitly.
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    await (this.program.methods as any)
+      .consumeRoll(idSuffix(unitId))
+      .accounts({ player, rarityResult: resultPDA })
+      .rpc();
   }
 
   /** Poll until the VRF callback fulfills the result (or timeout). */
@@ -132,12 +437,19 @@ export class OnChainVRFService implements VRFService {
 export class LocalVRFService implements VRFService {
   private results = new Map<string, RarityRollResult>();
 
-  async requestRarityRoll(nonce: number): Promise<{
+  private nonce = 0;
+
+  async startGameSession(_request: GameSessionRequest): Promise<number | null> {
+    return null;
+  }
+
+  async requestRarityRoll(_request: RarityRollRequest): Promise<{
     txSignature: string;
     resultPDA: PublicKey;
   }> {
-    const roll = Math.floor(Math.random() * 100);
-    const rarity = rarityFromRoll(roll);
+    const nonce = this.nonce++;
+    const roll = Math.floor(Math.random() * ROLL_DENOMINATOR);
+    const rarity = await rarityFromRoll(roll);
 
     // Deterministic fake PDA so getRarityResult can find it
     const nonceBuf = new BN(nonce).toArrayLike(Buffer, 'le', 8);
@@ -160,6 +472,14 @@ export class LocalVRFService implements VRFService {
   async getRarityResult(resultPDA: PublicKey): Promise<RarityRollResult | null> {
     return this.results.get(resultPDA.toBase58()) ?? null;
   }
+
+  async consumeRoll(_resultPDA: PublicKey, _unitId: string): Promise<void> {
+    // Local rolls have no ledger to consume from
+  }
+
+  recordTurn(_gameId: number, _turn: number, _actions: GameAction[], _era: number): void {
+    // Local games have no action chain to commit to
+  }
 }
 
 // ---------------------------------------------------------------------------
@@ -1,11 +1,160 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, Token2022};
+use anchor_spl::token_2022_extensions::spl_pod::optional_keys::OptionalNonZeroPubkey;
+use anchor_spl::token_2022_extensions::spl_token_metadata_interface::state::{
+    Field, TokenMetadata,
+};
+use anchor_spl::token_2022_extensions::{
+    token_metadata_initialize, token_metadata_update_field, TokenMetadataInitialize,
+    TokenMetadataUpdateField,
+};
+use anchor_spl::token_interface::{Mint, TokenAccount};
 use ephemeral_vrf_sdk::anchor::vrf;
 use ephemeral_vrf_sdk::instructions::{create_request_randomness_ix, RequestRandomnessParams};
 use ephemeral_vrf_sdk::types::SerializableAccountMeta;
+pub use rarity_math::{DEFAULT_RARITY_THRESHOLDS, ROLL_MAX};
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
+use solana_sdk_ids::{ed25519_program, sysvar};
+use solana_sha256_hasher::hashv;
 
 declare_id!("8U41n8DFkJUiyrxzCLpNQyvAAbHfnoD2GvRpCxQxiMaQ");
 
 pub const RARITY_SEED: &[u8] = b"rarity";
+pub const CONFIG_SEED: &[u8] = b"config";
+pub const RARITY_BATCH_SEED: &[u8] = b"rarity_batch";
+pub const PLAYER_STATS_SEED: &[u8] = b"player_stats";
+pub const GAME_SESSION_SEED: &[u8] = b"game_session";
+pub const GAME_CONTEXT_SEED: &[u8] = b"game_context";
+pub const ROLL_LEDGER_SEED: &[u8] = b"roll_ledger";
+pub const LOOTBOX_SEED: &[u8] = b"lootbox";
+pub const GOLDEN_AGE_SEED: &[u8] = b"golden_age";
+pub const PROBABILITY_CHECK_SEED: &[u8] = b"probability_check";
+pub const COLLECTIBLE_SEED: &[u8] = b"collectible";
+pub const WEIGHTED_TABLE_SEED: &[u8] = b"weighted_table";
+pub const WEIGHTED_ROLL_SEED: &[u8] = b"weighted_roll";
+pub const SOAR_AUTHORITY_SEED: &[u8] = b"soar_authority";
+pub const SCORE_SUBMISSION_SEED: &[u8] = b"score_submission";
+pub const GAME_RECORD_SEED: &[u8] = b"game_record";
+pub const VERIFIER_REGISTRY_SEED: &[u8] = b"verifier_registry";
+pub const ATTESTED_RESULT_SEED: &[u8] = b"attested_result";
+pub const ACTION_CHAIN_SEED: &[u8] = b"action_chain";
+pub const ACHIEVEMENT_SEED: &[u8] = b"achievement";
+pub const ACHIEVEMENT_UNLOCK_SEED: &[u8] = b"achievement_unlock";
+
+/// Maximum number of rolls a single `roll_rarity_batch` request can resolve.
+pub const MAX_BATCH_ROLLS: usize = 10;
+
+/// Current `RarityResult` account layout version. The baseline layout had
+/// no version byte (its first byte is the player key), so v1 accounts are
+/// told apart by length instead; see `LEGACY_RARITY_RESULT_LEN`.
+pub const RARITY_RESULT_VERSION: u8 = 2;
+
+/// Size of a baseline (v1) `RarityResult`, discriminator included.
+pub const LEGACY_RARITY_RESULT_LEN: usize = 52;
+
+/// Maximum number of rarity tiers a `RarityConfig` can hold.
+pub const MAX_RARITY_TIERS: usize = 8;
+
+/// Number of `UnitClass` variants, i.e. rarity tables held by `RarityConfig`.
+pub const UNIT_CLASS_COUNT: usize = 5;
+
+/// Slots a roll must wait unfulfilled before the player may cancel it (~2 min).
+pub const STALE_ROLL_SLOTS: u64 = 300;
+
+/// Tier guaranteed by bad-luck protection (Rare).
+pub const PITY_MIN_RARITY: u8 = 2;
+
+/// Consecutive sub-Rare rolls after which the next roll is guaranteed Rare.
+pub const DEFAULT_PITY_THRESHOLD: u8 = 10;
+
+/// Most players a game can seat, one per tribe.
+pub const MAX_PLAYERS: u8 = 6;
+
+/// Last turn of a game, mirrors `MAX_TURNS` in game-core.
+pub const MAX_TURNS: u16 = 50;
+
+/// Lootbox reward odds, indexed by `LootboxReward`; mirrors `REWARD_WEIGHTS`
+/// in game-core.
+pub const LOOTBOX_REWARD_WEIGHTS: [u16; 5] = [30, 15, 20, 20, 15];
+
+/// Airdrop gold range (inclusive).
+pub const AIRDROP_GOLD_MIN: u8 = 25;
+pub const AIRDROP_GOLD_MAX: u8 = 50;
+
+/// Golden age effects available per era (eras 1-3), mirrors the lengths of
+/// `EFFECTS_BY_ERA` in game-core. Effect indices follow that list's order.
+pub const GOLDEN_AGE_EFFECTS_PER_ERA: [u8; 3] = [6, 6, 5];
+
+/// Lowest tier (Epic) whose roll can back a collectible.
+pub const COLLECTIBLE_MIN_RARITY: u8 = 3;
+
+/// Token-2022 metadata symbol for unit collectibles.
+pub const COLLECTIBLE_SYMBOL: &str = "TRIBES";
+
+/// Display names for the launch tiers, indexed by rarity.
+pub const RARITY_NAMES: [&str; 5] = ["Common", "Uncommon", "Rare", "Epic", "Legendary"];
+
+/// Outcome and label limits for a `WeightedTable`.
+pub const MAX_WEIGHTED_OUTCOMES: usize = 16;
+pub const MAX_OUTCOME_LABEL_LEN: usize = 32;
+
+/// Weighted tables `0..UNIT_CLASS_COUNT` hold each unit class's rarity odds,
+/// with rarity tiers as outcomes. `register_rarity_table` creates them from
+/// the config and `update_config` keeps them in step with it.
+pub const RARITY_WEIGHTED_TABLES: u32 = UNIT_CLASS_COUNT as u32;
+
+/// MagicBlock SOAR leaderboard program.
+pub const SOAR_PROGRAM_ID: Pubkey = pubkey!("SoarNNzwQHMwcfdkdLc6kvbkoMSxcHy89gTHrjhJYkk");
+
+/// SOAR's own PDA seeds: player profile `[b"player", user]` and score list
+/// `[b"player-scores-list", player_account, leaderboard]`.
+pub const SOAR_PLAYER_SEED: &[u8] = b"player";
+pub const SOAR_PLAYER_SCORES_SEED: &[u8] = b"player-scores-list";
+
+/// Anchor discriminator of SOAR's `submit_score`.
+const SOAR_SUBMIT_SCORE_DISCRIMINATOR: [u8; 8] = [212, 128, 45, 22, 112, 82, 85, 235];
+
+/// Game rule bounds for `finalize_game`, mirroring game-core's content.
+pub const MAX_TECHS: u8 = 30;
+pub const MAX_WONDERS: u8 = 10;
+pub const MAX_GREAT_PEOPLE: u8 = 11;
+/// 7 universal golden age triggers plus one tribal trigger.
+pub const MAX_GOLDEN_AGES: u8 = 8;
+/// game-core places 4–6 lootboxes per map, `lootbox_1` to `lootbox_6`.
+pub const MAX_LOOTBOXES: u8 = 6;
+
+/// Content sizes from game-core that bound the terms of
+/// `calculateFloorPrice`; `GameSummary::floor_price_ceiling` sums them.
+/// 20×20 map (`MAP_WIDTH`, `MAP_HEIGHT`).
+pub const MAP_TILES: u64 = 400;
+/// Settlement `MAX_LEVEL`; each level is worth 5 and one population.
+pub const MAX_SETTLEMENT_LEVEL: u64 = 25;
+/// Entries in `CULTURE_DEFINITIONS`, worth 5 each.
+pub const MAX_CULTURES: u64 = 28;
+/// Highest wonder `floorPriceBonus`.
+pub const MAX_WONDER_FLOOR_PRICE: u64 = 75;
+/// `MAX_MILITARY_STACK` (2) plus `MAX_CIVILIAN_STACK` (1).
+pub const MAX_UNITS_PER_TILE: u64 = 3;
+/// A legendary unit: 2 plus a rarity bonus of 10.
+pub const MAX_UNIT_FLOOR_PRICE: u64 = 12;
+/// Gold is the one term content does not bound: income, trades, great
+/// people and refunds all feed the treasury, which scores 1 per 10 gold.
+/// This allowance (1000 gold a turn) is a policy choice, not a derivation.
+pub const MAX_GOLD_FLOOR_PRICE_PER_TURN: u64 = 100;
+
+/// Replay verifier keys the registry can hold.
+pub const MAX_VERIFIERS: usize = 4;
+
+/// Attested message: player (32) || game seed (32) || action log hash (32)
+/// || score (8, little-endian).
+pub const ATTESTATION_MESSAGE_LEN: usize = 104;
+
+/// Longest achievement slug, e.g. `"empire_builder"`.
+pub const MAX_ACHIEVEMENT_SLUG_LEN: usize = 32;
 
 #[program]
 pub mod vrf_rarity {
@@ -13,119 +162,3849 @@ pub mod vrf_rarity {
 
     /// Request a provably-fair rarity roll via MagicBlock VRF.
     /// Creates a PDA to store the result and CPIs into the VRF oracle.
-    pub fn roll_rarity(ctx: Context<RollRarityCtx>, nonce: u64) -> Result<()> {
+    /// The roll resolves against the table for `unit_class` and is bound to
+    /// the game context's current turn, a settlement and a unit type, so the
+    /// roll used for each mint can be audited. `nonce` must be the next one
+    /// handed out by the player's `RollLedger`.
+    pub fn roll_rarity(
+        ctx: Context<RollRarityCtx>,
+        nonce: u64,
+        unit_class: UnitClass,
+        turn: u16,
+        settlement_id: u32,
+        unit_type: u8,
+    ) -> Result<()> {
+        let context = &ctx.accounts.game_context;
+        context.check_roll(ctx.accounts.payer.key(), turn)?;
+
         let result = &mut ctx.accounts.rarity_result;
+        // A live account at this PDA means the nonce was already rolled
+        require!(
+            result.player == Pubkey::default(),
+            VrfRarityError::InvalidNonce
+        );
+        let slot = Clock::get()?.slot;
+        result.version = RARITY_RESULT_VERSION;
         result.player = ctx.accounts.payer.key();
         result.nonce = nonce;
+        result.unit_class = unit_class;
+        result.game_id = context.game_id;
+        result.turn = turn;
+        result.settlement_id = settlement_id;
+        result.unit_type = unit_type;
         result.rarity = 0;
         result.fulfilled = false;
         result.roll_value = 0;
+        result.requested_slot = slot;
+        result.config_version = ctx.accounts.config.version;
         result.bump = ctx.bumps.rarity_result;
 
-        // Pad nonce into a 32-byte caller seed
-        let mut caller_seed = [0u8; 32];
-        caller_seed[..8].copy_from_slice(&nonce.to_le_bytes());
-
-        let ix = create_request_randomness_ix(RequestRandomnessParams {
-            payer: ctx.accounts.payer.key(),
-            oracle_queue: ctx.accounts.oracle_queue.key(),
-            callback_program_id: ID,
-            callback_discriminator: instruction::CallbackRollRarity::DISCRIMINATOR.to_vec(),
-            caller_seed,
-            accounts_metas: Some(vec![SerializableAccountMeta {
-                pubkey: ctx.accounts.rarity_result.key(),
-                is_signer: false,
-                is_writable: true,
-            }]),
-            ..Default::default()
-        });
+        ctx.accounts
+            .player_stats
+            .init_if_new(ctx.accounts.payer.key(), ctx.bumps.player_stats);
+
+        let ledger = &mut ctx.accounts.roll_ledger;
+        ledger.init_if_new(ctx.accounts.payer.key(), ctx.bumps.roll_ledger);
+        ledger.assign(nonce)?;
+
+        let ix = vrf_request_ix(
+            ctx.accounts.payer.key(),
+            ctx.accounts.oracle_queue.key(),
+            instruction::CallbackRollRarity::DISCRIMINATOR,
+            caller_seed(nonce, SEED_KIND_SINGLE),
+            vec![
+                callback_meta(ctx.accounts.rarity_result.key(), true),
+                callback_meta(ctx.accounts.config.key(), false),
+                callback_meta(ctx.accounts.player_stats.key(), true),
+            ],
+        );
 
         ctx.accounts
             .invoke_signed_vrf(&ctx.accounts.payer.to_account_info(), &ix)?;
 
+        emit!(RarityRequested {
+            player: ctx.accounts.payer.key(),
+            nonce,
+            result_pda: ctx.accounts.rarity_result.key(),
+            slot,
+        });
+
         Ok(())
     }
 
     /// Callback invoked by the MagicBlock VRF program with verified randomness.
-    /// Maps the random byte to a rarity tier and stores the result in the PDA.
+    /// Maps the random byte to a rarity tier using the on-chain rarity table,
+    /// which must still be at the version pinned by the request, and stores
+    /// the result in the PDA. The raw randomness is kept so anyone can
+    /// re-derive the roll and its tier. Bad-luck protection may raise the
+    /// tier to Rare; `pity_applied` records it.
     pub fn callback_roll_rarity(
         ctx: Context<CallbackRollRarityCtx>,
         randomness: [u8; 32],
     ) -> Result<()> {
-        // Random value in [0, 99]
-        let roll = ephemeral_vrf_sdk::rnd::random_u8_with_range(&randomness, 0, 99);
+        // Uniform value in basis points, [0, 9999]
+        let roll = rarity_math::roll_from_randomness(&randomness);
 
-        // Rarity mapping:
-        //   0-49  = Common     (50%)
-        //   50-79 = Uncommon   (30%)
-        //   80-94 = Rare       (15%)
-        //   95-98 = Epic       (4%)
-        //   99    = Legendary  (1%)
-        let rarity = if roll <= 49 {
-            0
-        } else if roll <= 79 {
-            1
-        } else if roll <= 94 {
-            2
-        } else if roll <= 98 {
-            3
-        } else {
-            4
-        };
+        let config = &ctx.accounts.config;
+        let result = &mut ctx.accounts.rarity_result;
+        let table = config.table(result.unit_class);
+        let (rarity, pity_applied) = ctx.accounts.player_stats.apply_roll(
+            table.tier_for_roll(roll),
+            config.pity_threshold,
+            table,
+        );
+        require!(
+            rarity < table.tier_count,
+            VrfRarityError::InvalidRarityIndex
+        );
 
         msg!("VRF rarity roll: {} -> rarity {}", roll, rarity);
 
-        let result = &mut ctx.accounts.rarity_result;
+        let clock = Clock::get()?;
         result.rarity = rarity;
         result.roll_value = roll;
+        result.randomness = randomness;
+        result.fulfilled_slot = clock.slot;
+        result.fulfilled_at = clock.unix_timestamp;
+        result.pity_applied = pity_applied;
         result.fulfilled = true;
 
+        emit!(RarityFulfilled {
+            player: result.player,
+            nonce: result.nonce,
+            rarity,
+            roll_value: roll,
+            slot: clock.slot,
+        });
+
         Ok(())
     }
-}
 
-// ---- Account Contexts ----
+    /// Request `count` rarity rolls backed by a single VRF request, so a
+    /// player with several queued mints pays one oracle fee and signs once.
+    /// Every slot resolves against the table for `unit_class`, and the batch
+    /// is bound to the game context's current turn. The batch takes the next
+    /// nonce from the player's `RollLedger`.
+    pub fn roll_rarity_batch(
+        ctx: Context<RollRarityBatchCtx>,
+        nonce: u64,
+        count: u8,
+        unit_class: UnitClass,
+        turn: u16,
+    ) -> Result<()> {
+        let context = &ctx.accounts.game_context;
+        context.check_roll(ctx.accounts.payer.key(), turn)?;
 
-#[vrf]
-#[derive(Accounts)]
-#[instruction(nonce: u64)]
-pub struct RollRarityCtx<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
+        require!(
+            count > 0 && count as usize <= MAX_BATCH_ROLLS,
+            VrfRarityError::InvalidBatchCount
+        );
 
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + RarityResult::INIT_SPACE,
-        seeds = [RARITY_SEED, payer.key().as_ref(), &nonce.to_le_bytes()],
-        bump
-    )]
-    pub rarity_result: Account<'info, RarityResult>,
+        let batch = &mut ctx.accounts.batch_result;
+        require!(
+            batch.player == Pubkey::default(),
+            VrfRarityError::InvalidNonce
+        );
+        batch.player = ctx.accounts.payer.key();
+        batch.nonce = nonce;
+        batch.count = count;
+        batch.unit_class = unit_class;
+        batch.game_id = context.game_id;
+        batch.turn = turn;
+        batch.fulfilled = false;
+        batch.requested_slot = Clock::get()?.slot;
+        batch.config_version = ctx.accounts.config.version;
+        batch.bump = ctx.bumps.batch_result;
 
-    /// CHECK: MagicBlock oracle queue
-    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
-    pub oracle_queue: AccountInfo<'info>,
-}
+        ctx.accounts
+            .player_stats
+            .init_if_new(ctx.accounts.payer.key(), ctx.bumps.player_stats);
 
-#[derive(Accounts)]
-pub struct CallbackRollRarityCtx<'info> {
-    /// The VRF program identity PDA — proves this CPI originates from the VRF program
-    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
-    pub vrf_program_identity: Signer<'info>,
+        let ledger = &mut ctx.accounts.roll_ledger;
+        ledger.init_if_new(ctx.accounts.payer.key(), ctx.bumps.roll_ledger);
+        ledger.assign(nonce)?;
 
-    #[account(mut)]
-    pub rarity_result: Account<'info, RarityResult>,
-}
+        let ix = vrf_request_ix(
+            ctx.accounts.payer.key(),
+            ctx.accounts.oracle_queue.key(),
+            instruction::CallbackRollRarityBatch::DISCRIMINATOR,
+            caller_seed(nonce, SEED_KIND_BATCH),
+            vec![
+                callback_meta(ctx.accounts.batch_result.key(), true),
+                callback_meta(ctx.accounts.config.key(), false),
+                callback_meta(ctx.accounts.player_stats.key(), true),
+            ],
+        );
 
-// ---- State ----
+        ctx.accounts
+            .invoke_signed_vrf(&ctx.accounts.payer.to_account_info(), &ix)?;
 
-#[account]
-#[derive(InitSpace)]
-pub struct RarityResult {
-    pub player: Pubkey,  // 32 — wallet that requested the roll
-    pub nonce: u64,      //  8 — unique roll identifier
-    pub rarity: u8,      //  1 — 0=Common 1=Uncommon 2=Rare 3=Epic 4=Legendary
-    pub fulfilled: bool, //  1 — true after VRF callback
-    pub roll_value: u8,  //  1 — raw random value [0, 99]
-    pub bump: u8,        //  1 — PDA bump seed
+        Ok(())
+    }
+
+    /// Callback for `roll_rarity_batch`. Each slot gets its own 32-byte value
+    /// derived as `sha256(randomness || slot_index)`, so slots are independent
+    /// and can each be re-derived from the oracle output. As for single
+    /// rolls, the config must still be at the version pinned by the request.
+    pub fn callback_roll_rarity_batch(
+        ctx: Context<CallbackRollRarityBatchCtx>,
+        randomness: [u8; 32],
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let batch = &mut ctx.accounts.batch_result;
+        let stats = &mut ctx.accounts.player_stats;
+        let table = config.table(batch.unit_class);
+
+        for i in 0..batch.count {
+            let roll = rarity_math::roll_from_randomness(&sub_randomness(&randomness, i));
+            let (rarity, pity_applied) =
+                stats.apply_roll(table.tier_for_roll(roll), config.pity_threshold, table);
+            require!(
+                rarity < table.tier_count,
+                VrfRarityError::InvalidRarityIndex
+            );
+            batch.rolls[i as usize] = RarityRoll {
+                rarity,
+                roll_value: roll,
+                pity_applied,
+            };
+        }
+
+        msg!("VRF rarity batch: {} rolls fulfilled", batch.count);
+
+        batch.fulfilled = true;
+
+        Ok(())
+    }
+
+    /// Close a fulfilled batch result and refund its rent to the player.
+    pub fn close_rarity_batch(_ctx: Context<CloseRarityBatchCtx>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Close a fulfilled rarity result and refund its rent to the player.
+    /// Unfulfilled results stay open so a pending oracle callback never
+    /// lands on a closed account.
+    pub fn close_rarity_result(_ctx: Context<CloseRarityResultCtx>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Close a baseline (v1) rarity result, which no longer deserializes as
+    /// a `RarityResult`, and refund its rent to the player. The account is
+    /// checked on its raw bytes: owner, discriminator, v1 length and player.
+    pub fn close_legacy_rarity_result(
+        ctx: Context<CloseLegacyRarityResultCtx>,
+        _nonce: u64,
+    ) -> Result<()> {
+        let legacy = ctx.accounts.legacy_result.to_account_info();
+        require!(
+            is_legacy_rarity_result(&legacy.try_borrow_data()?, &ctx.accounts.player.key()),
+            VrfRarityError::NotLegacyResult
+        );
+
+        let player = ctx.accounts.player.to_account_info();
+        **player.lamports.borrow_mut() += legacy.lamports();
+        **legacy.lamports.borrow_mut() = 0;
+        legacy.assign(&system_program::ID);
+        legacy.resize(0)?;
+        Ok(())
+    }
+
+    /// Cancel a roll the oracle never fulfilled, once `STALE_ROLL_SLOTS` have
+    /// passed since the request. Closes the PDA, refunds rent, and leaves a
+    /// `RarityRollCancelled` event plus a counter in `PlayerRollStats` so the
+    /// fallback is visible. The ledger moves past the nonce, so it is not
+    /// counted as skipped. Re-roll with a fresh nonce.
+    pub fn cancel_stale_roll(ctx: Context<CancelStaleRollCtx>) -> Result<()> {
+        let result = &ctx.accounts.rarity_result;
+        let slot = Clock::get()?.slot;
+        require!(
+            slot >= result.requested_slot.saturating_add(STALE_ROLL_SLOTS),
+            VrfRarityError::RollNotStale
+        );
+
+        let stats = &mut ctx.accounts.player_stats;
+        stats.cancelled_rolls = stats.cancelled_rolls.saturating_add(1);
+        let skipped = ctx.accounts.roll_ledger.cancel(result.nonce);
+
+        emit!(RarityRollCancelled {
+            player: result.player,
+            nonce: result.nonce,
+            requested_slot: result.requested_slot,
+            cancelled_slot: slot,
+            skipped,
+        });
+
+        Ok(())
+    }
+
+    /// Batch counterpart of `cancel_stale_roll`: closes an unfulfilled batch
+    /// once `STALE_ROLL_SLOTS` have passed, refunds rent and records every
+    /// slot as a cancelled roll.
+    pub fn cancel_stale_batch(ctx: Context<CancelStaleBatchCtx>) -> Result<()> {
+        let batch = &ctx.accounts.batch_result;
+        let slot = Clock::get()?.slot;
+        require!(
+            slot >= batch.requested_slot.saturating_add(STALE_ROLL_SLOTS),
+            VrfRarityError::RollNotStale
+        );
+
+        let stats = &mut ctx.accounts.player_stats;
+        stats.cancelled_rolls = stats.cancelled_rolls.saturating_add(batch.count as u32);
+        let skipped = ctx.accounts.roll_ledger.cancel(batch.nonce);
+
+        emit!(RarityBatchCancelled {
+            player: batch.player,
+            nonce: batch.nonce,
+            count: batch.count,
+            requested_slot: batch.requested_slot,
+            cancelled_slot: slot,
+            skipped,
+        });
+
+        Ok(())
+    }
+
+    /// Create the rarity tables, seeding every unit class with `thresholds`.
+    /// Only the program's upgrade authority may call this; the signer becomes
+    /// the config's authority.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfigCtx>,
+        thresholds: Vec<u16>,
+    ) -> Result<()> {
+        let table = RarityTable::new(&thresholds)?;
+
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.version = 1;
+        config.tables = [table; UNIT_CLASS_COUNT];
+        config.pity_threshold = DEFAULT_PITY_THRESHOLD;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    /// Replace the rarity table for one unit class, and the class's rarity
+    /// `WeightedTable` with it. Bumps the version so rolls resolved against
+    /// the old table remain distinguishable; rolls still in flight can no
+    /// longer be fulfilled and are cancelled once stale.
+    pub fn update_config(
+        ctx: Context<UpdateRarityTableCtx>,
+        unit_class: UnitClass,
+        thresholds: Vec<u16>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.tables[unit_class as usize] = RarityTable::new(&thresholds)?;
+        config.version += 1;
+
+        let weighted_table = &mut ctx.accounts.weighted_table;
+        weighted_table.set_rarity(config.table(unit_class))?;
+        weighted_table.version += 1;
+        Ok(())
+    }
+
+    /// Register `unit_class`'s rarity odds as a `WeightedTable`, so
+    /// `roll_weighted` can pick a tier from them (without bad-luck
+    /// protection). The table id is the class index; see
+    /// `RARITY_WEIGHTED_TABLES`.
+    pub fn register_rarity_table(
+        ctx: Context<RegisterRarityTableCtx>,
+        unit_class: UnitClass,
+    ) -> Result<()> {
+        let table = &mut ctx.accounts.weighted_table;
+        table.set_rarity(ctx.accounts.config.table(unit_class))?;
+        table.authority = ctx.accounts.authority.key();
+        table.table_id = unit_class.weighted_table_id();
+        table.version = 1;
+        table.bump = ctx.bumps.weighted_table;
+        Ok(())
+    }
+
+    /// Set how many consecutive sub-Rare rolls trigger a guaranteed Rare.
+    /// Zero disables bad-luck protection.
+    pub fn update_pity_threshold(ctx: Context<UpdateConfigCtx>, pity_threshold: u8) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.pity_threshold = pity_threshold;
+        config.version += 1;
+        Ok(())
+    }
+
+    /// Start a ranked game whose map seed nobody picked. Creates the
+    /// `GameSession` PDA and requests VRF randomness; the callback stores
+    /// the 32-byte map seed that drives `generateMap`.
+    pub fn start_game(
+        ctx: Context<StartGameCtx>,
+        game_id: u64,
+        tribe: Tribe,
+        map_width: u8,
+        map_height: u8,
+        player_count: u8,
+    ) -> Result<()> {
+        require!(
+            map_width > 0 && map_height > 0,
+            VrfRarityError::InvalidGameSettings
+        );
+        require!(
+            (1..=MAX_PLAYERS).contains(&player_count),
+            VrfRarityError::InvalidGameSettings
+        );
+
+        let session = &mut ctx.accounts.game_session;
+        require!(
+            session.player == Pubkey::default(),
+            VrfRarityError::GameAlreadyStarted
+        );
+        session.player = ctx.accounts.payer.key();
+        session.game_id = game_id;
+        session.tribe = tribe;
+        session.map_width = map_width;
+        session.map_height = map_height;
+        session.player_count = player_count;
+        session.fulfilled = false;
+        session.requested_slot = Clock::get()?.slot;
+        session.bump = ctx.bumps.game_session;
+
+        let ix = vrf_request_ix(
+            ctx.accounts.payer.key(),
+            ctx.accounts.oracle_queue.key(),
+            instruction::CallbackStartGame::DISCRIMINATOR,
+            caller_seed(game_id, SEED_KIND_GAME),
+            vec![callback_meta(ctx.accounts.game_session.key(), true)],
+        );
+
+        ctx.accounts
+            .invoke_signed_vrf(&ctx.accounts.payer.to_account_info(), &ix)?;
+
+        Ok(())
+    }
+
+    /// Callback for `start_game`. Stores the VRF output as the map seed.
+    pub fn callback_start_game(
+        ctx: Context<CallbackStartGameCtx>,
+        randomness: [u8; 32],
+    ) -> Result<()> {
+        let session = &mut ctx.accounts.game_session;
+        session.seed = randomness;
+        session.fulfilled_slot = Clock::get()?.slot;
+        session.fulfilled = true;
+
+        msg!("VRF game seed: game {}", session.game_id);
+
+        Ok(())
+    }
+
+    /// Open the per-game context that rarity rolls are bound to. Starts at
+    /// turn 1 in era 1, matching `createInitialState`.
+    pub fn open_game_context(ctx: Context<OpenGameContextCtx>, game_id: u64) -> Result<()> {
+        let context = &mut ctx.accounts.game_context;
+        context.player = ctx.accounts.player.key();
+        context.game_id = game_id;
+        context.current_turn = 1;
+        context.era = 1;
+        context.bump = ctx.bumps.game_context;
+        Ok(())
+    }
+
+    /// Move the game context to the next turn, in the player's tech `era`
+    /// at its start. The turn being left must already be committed to the
+    /// game's `ActionChain`, so every turn rolls were made on, and the era
+    /// reported for it, are part of the replayable action log.
+    pub fn advance_game_turn(ctx: Context<UpdateGameContextCtx>, turn: u16, era: u8) -> Result<()> {
+        let committed_turn = ctx.accounts.action_chain.last_turn;
+        ctx.accounts.game_context.advance(turn, era, committed_turn)
+    }
+
+    /// Close a game context and refund its rent to the player.
+    pub fn close_game_context(_ctx: Context<CloseGameContextCtx>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Mark a fulfilled roll as used for `unit_id` (numeric suffix of the
+    /// minted UnitId). Rolls must be consumed in nonce order; any earlier
+    /// nonce that was never consumed is recorded as skipped in the ledger.
+    pub fn consume_roll(ctx: Context<ConsumeRollCtx>, unit_id: u32) -> Result<()> {
+        let result = &mut ctx.accounts.rarity_result;
+        let skipped = ctx.accounts.roll_ledger.consume(result.nonce)?;
+        result.consumed = true;
+        result.consumed_unit_id = unit_id;
+
+        emit!(RollConsumed {
+            player: result.player,
+            nonce: result.nonce,
+            slot_index: 0,
+            unit_id,
+            skipped,
+        });
+
+        Ok(())
+    }
+
+    /// Mark the next slot of a fulfilled batch as used for `unit_id`. Slots
+    /// are consumed in order; the batch's nonce is consumed with slot 0.
+    pub fn consume_batch_roll(
+        ctx: Context<ConsumeBatchRollCtx>,
+        slot_index: u8,
+        unit_id: u32,
+    ) -> Result<()> {
+        let batch = &mut ctx.accounts.batch_result;
+        let skipped = batch.consume_slot(slot_index, unit_id, &mut ctx.accounts.roll_ledger)?;
+
+        emit!(RollConsumed {
+            player: batch.player,
+            nonce: batch.nonce,
+            slot_index,
+            unit_id,
+            skipped,
+        });
+
+        Ok(())
+    }
+
+    /// Request a VRF-backed lootbox reward for `lootbox_id` in a seeded
+    /// ranked game. The callback picks the reward and its parameters. Each
+    /// session opens at most `MAX_LOOTBOXES`, with ids in `1..=MAX_LOOTBOXES`.
+    pub fn roll_lootbox(ctx: Context<RollLootboxCtx>, lootbox_id: u32) -> Result<()> {
+        ctx.accounts.game_session.open_lootbox(lootbox_id)?;
+
+        let lootbox = &mut ctx.accounts.lootbox_result;
+        lootbox.player = ctx.accounts.payer.key();
+        lootbox.game_session = ctx.accounts.game_session.key();
+        lootbox.lootbox_id = lootbox_id;
+        lootbox.fulfilled = false;
+        lootbox.bump = ctx.bumps.lootbox_result;
+
+        let ix = vrf_request_ix(
+            ctx.accounts.payer.key(),
+            ctx.accounts.oracle_queue.key(),
+            instruction::CallbackRollLootbox::DISCRIMINATOR,
+            caller_seed(lootbox_id as u64, SEED_KIND_LOOTBOX),
+            vec![
+                callback_meta(ctx.accounts.lootbox_result.key(), true),
+                callback_meta(ctx.accounts.config.key(), false),
+            ],
+        );
+
+        ctx.accounts
+            .invoke_signed_vrf(&ctx.accounts.payer.to_account_info(), &ix)?;
+
+        Ok(())
+    }
+
+    /// Callback for `roll_lootbox`. The reward, airdrop gold and the OG
+    /// Holder unit's rarity each come from their own derived randomness.
+    pub fn callback_roll_lootbox(
+        ctx: Context<CallbackRollLootboxCtx>,
+        randomness: [u8; 32],
+    ) -> Result<()> {
+        let reward_index =
+            rarity_math::pick_weighted(&LOOTBOX_REWARD_WEIGHTS, &sub_randomness(&randomness, 0))
+                .ok_or(VrfRarityError::InvalidRarityIndex)?;
+        let gold_span = (AIRDROP_GOLD_MAX - AIRDROP_GOLD_MIN + 1) as u64;
+        let gold = AIRDROP_GOLD_MIN
+            + rarity_math::uniform_below(&sub_randomness(&randomness, 1), gold_span) as u8;
+        // OG Holder always spawns a warrior, so it uses the melee table
+        let unit_roll = rarity_math::roll_from_randomness(&sub_randomness(&randomness, 2));
+        let unit_rarity = ctx
+            .accounts
+            .config
+            .table(UnitClass::Melee)
+            .tier_for_roll(unit_roll);
+
+        let lootbox = &mut ctx.accounts.lootbox_result;
+        lootbox.reward = LootboxReward::from_index(reward_index);
+        lootbox.gold_amount = gold;
+        lootbox.unit_rarity = unit_rarity;
+        lootbox.unit_roll_value = unit_roll;
+        lootbox.randomness = randomness;
+        lootbox.fulfilled = true;
+
+        msg!(
+            "VRF lootbox {}: reward {}",
+            lootbox.lootbox_id,
+            reward_index
+        );
+
+        Ok(())
+    }
+
+    /// Request a VRF-selected golden age effect on the context's current
+    /// turn of a seeded ranked game, from the context's era.
+    pub fn roll_golden_age_effect(ctx: Context<RollGoldenAgeEffectCtx>) -> Result<()> {
+        let era = ctx.accounts.game_context.era;
+        let effect_count = golden_age_effect_count(era).ok_or(VrfRarityError::InvalidEra)?;
+
+        let golden_age = &mut ctx.accounts.golden_age_result;
+        golden_age.player = ctx.accounts.payer.key();
+        golden_age.game_session = ctx.accounts.game_session.key();
+        golden_age.turn = ctx.accounts.game_context.current_turn;
+        golden_age.era = era;
+        golden_age.effect_count = effect_count;
+        golden_age.fulfilled = false;
+        golden_age.bump = ctx.bumps.golden_age_result;
+
+        let ix = vrf_request_ix(
+            ctx.accounts.payer.key(),
+            ctx.accounts.oracle_queue.key(),
+            instruction::CallbackRollGoldenAgeEffect::DISCRIMINATOR,
+            caller_seed(golden_age.turn as u64, SEED_KIND_GOLDEN_AGE),
+            vec![callback_meta(ctx.accounts.golden_age_result.key(), true)],
+        );
+
+        ctx.accounts
+            .invoke_signed_vrf(&ctx.accounts.payer.to_account_info(), &ix)?;
+
+        Ok(())
+    }
+
+    /// Callback for `roll_golden_age_effect`: picks an index into the era's
+    /// effect list.
+    pub fn callback_roll_golden_age_effect(
+        ctx: Context<CallbackRollGoldenAgeEffectCtx>,
+        randomness: [u8; 32],
+    ) -> Result<()> {
+        let golden_age = &mut ctx.accounts.golden_age_result;
+        let effect_index =
+            rarity_math::uniform_below(&randomness, golden_age.effect_count as u64) as u8;
+
+        golden_age.effect_index = effect_index;
+        golden_age.randomness = randomness;
+        golden_age.fulfilled = true;
+
+        msg!(
+            "VRF golden age: era {} effect {}",
+            golden_age.era,
+            effect_index
+        );
+
+        Ok(())
+    }
+
+    /// Request a VRF-backed `numerator / denominator` chance check for a
+    /// seeded ranked game. `tag` names what is being checked (e.g. a great
+    /// person id) and, with `turn`, keys the result PDA.
+    pub fn roll_probability_check(
+        ctx: Context<RollProbabilityCheckCtx>,
+        tag: u32,
+        turn: u16,
+        numerator: u32,
+        denominator: u32,
+    ) -> Result<()> {
+        require!(
+            denominator > 0 && numerator <= denominator,
+            VrfRarityError::InvalidProbability
+        );
+        ctx.accounts
+            .game_context
+            .check_roll(ctx.accounts.payer.key(), turn)?;
+
+        let check = &mut ctx.accounts.probability_check;
+        check.player = ctx.accounts.payer.key();
+        check.game_session = ctx.accounts.game_session.key();
+        check.tag = tag;
+        check.turn = turn;
+        check.numerator = numerator;
+        check.denominator = denominator;
+        check.fulfilled = false;
+        check.bump = ctx.bumps.probability_check;
+
+        let ix = vrf_request_ix(
+            ctx.accounts.payer.key(),
+            ctx.accounts.oracle_queue.key(),
+            instruction::CallbackProbabilityCheck::DISCRIMINATOR,
+            caller_seed(((tag as u64) << 16) | turn as u64, SEED_KIND_PROBABILITY),
+            vec![callback_meta(ctx.accounts.probability_check.key(), true)],
+        );
+
+        ctx.accounts
+            .invoke_signed_vrf(&ctx.accounts.payer.to_account_info(), &ix)?;
+
+        Ok(())
+    }
+
+    /// Callback for `roll_probability_check`: records pass or fail.
+    pub fn callback_probability_check(
+        ctx: Context<CallbackProbabilityCheckCtx>,
+        randomness: [u8; 32],
+    ) -> Result<()> {
+        let check = &mut ctx.accounts.probability_check;
+        check.passed = rarity_math::passes_check(&randomness, check.numerator, check.denominator);
+        check.randomness = randomness;
+        check.fulfilled = true;
+
+        msg!(
+            "VRF probability check {} turn {}: {}/{} passed={}",
+            check.tag,
+            check.turn,
+            check.numerator,
+            check.denominator,
+            check.passed
+        );
+
+        Ok(())
+    }
+
+    /// Mint a one-of-one Token-2022 collectible for a fulfilled Epic or
+    /// better roll. The mint PDA is keyed by the roll, so each result backs
+    /// at most one collectible; mint authority is dropped after the single
+    /// token is issued.
+    pub fn mint_unit_collectible(ctx: Context<MintUnitCollectibleCtx>) -> Result<()> {
+        let result = &ctx.accounts.rarity_result;
+        let result_key = result.key();
+        let rarity = rarity_name(result.rarity);
+        let tribe = ctx.accounts.game_session.tribe.name();
+
+        let name = format!("{rarity} {tribe} Unit");
+        let fields = [
+            ("unit_type", result.unit_type.to_string()),
+            ("tribe", tribe.to_string()),
+            ("rarity", rarity.clone()),
+            ("rarity_result", result_key.to_string()),
+        ];
+
+        // Token-2022 reallocs the mint for the metadata but does not fund it
+        let metadata = TokenMetadata {
+            update_authority: OptionalNonZeroPubkey(ctx.accounts.collectible_mint.key()),
+            mint: ctx.accounts.collectible_mint.key(),
+            name: name.clone(),
+            symbol: COLLECTIBLE_SYMBOL.to_string(),
+            uri: String::new(),
+            additional_metadata: fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        };
+        let mint_info = ctx.accounts.collectible_mint.to_account_info();
+        let new_len = mint_info.data_len() + metadata.tlv_size_of()?;
+        let top_up = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(mint_info.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: mint_info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+
+        let bump = [ctx.bumps.collectible_mint];
+        let signer_seeds: &[&[&[u8]]] = &[&[COLLECTIBLE_SEED, result_key.as_ref(), &bump]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+
+        token_metadata_initialize(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                TokenMetadataInitialize {
+                    program_id: token_program.clone(),
+                    metadata: mint_info.clone(),
+                    update_authority: mint_info.clone(),
+                    mint_authority: mint_info.clone(),
+                    mint: mint_info.clone(),
+                },
+                signer_seeds,
+            ),
+            name,
+            COLLECTIBLE_SYMBOL.to_string(),
+            String::new(),
+        )?;
+
+        for (key, value) in fields {
+            token_metadata_update_field(
+                CpiContext::new_with_signer(
+                    token_program.clone(),
+                    TokenMetadataUpdateField {
+                        program_id: token_program.clone(),
+                        metadata: mint_info.clone(),
+                        update_authority: mint_info.clone(),
+                    },
+                    signer_seeds,
+                ),
+                Field::Key(key.to_string()),
+                value,
+            )?;
+        }
+
+        token_2022::mint_to(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                token_2022::MintTo {
+                    mint: mint_info.clone(),
+                    to: ctx.accounts.player_token_account.to_account_info(),
+                    authority: mint_info.clone(),
+                },
+                signer_seeds,
+            ),
+            1,
+        )?;
+
+        token_2022::set_authority(
+            CpiContext::new_with_signer(
+                token_program,
+                token_2022::SetAuthority {
+                    current_authority: mint_info.clone(),
+                    account_or_mint: mint_info,
+                },
+                signer_seeds,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        emit!(UnitCollectibleMinted {
+            player: ctx.accounts.payer.key(),
+            rarity_result: result_key,
+            mint: ctx.accounts.collectible_mint.key(),
+            rarity: result.rarity,
+            unit_type: result.unit_type,
+        });
+
+        Ok(())
+    }
+
+    /// Register a new weighted table. Only the config authority may add
+    /// tables; ids below `RARITY_WEIGHTED_TABLES` are reserved for rarity.
+    pub fn create_weighted_table(
+        ctx: Context<CreateWeightedTableCtx>,
+        table_id: u32,
+        weights: Vec<u16>,
+        labels: Vec<String>,
+    ) -> Result<()> {
+        require!(
+            table_id >= RARITY_WEIGHTED_TABLES,
+            VrfRarityError::ReservedWeightedTable
+        );
+        let table = &mut ctx.accounts.weighted_table;
+        table.set(weights, labels)?;
+        table.authority = ctx.accounts.authority.key();
+        table.table_id = table_id;
+        table.version = 1;
+        table.bump = ctx.bumps.weighted_table;
+        Ok(())
+    }
+
+    /// Replace a table's outcomes. Bumps the version so rolls resolved
+    /// against the old outcomes remain distinguishable; rolls still in flight
+    /// can no longer be fulfilled and are cancelled once stale. Rarity
+    /// tables only change through `update_config`.
+    pub fn update_weighted_table(
+        ctx: Context<UpdateWeightedTableCtx>,
+        weights: Vec<u16>,
+        labels: Vec<String>,
+    ) -> Result<()> {
+        let table = &mut ctx.accounts.weighted_table;
+        require!(
+            table.table_id >= RARITY_WEIGHTED_TABLES,
+            VrfRarityError::ReservedWeightedTable
+        );
+        table.set(weights, labels)?;
+        table.version += 1;
+        Ok(())
+    }
+
+    /// Request a VRF-backed pick from `weighted_table`. The callback stores
+    /// the outcome index in a `WeightedRollResult`. The table's version is
+    /// recorded now, so odds changed while the roll is in flight are caught.
+    pub fn roll_weighted(ctx: Context<RollWeightedCtx>, nonce: u64) -> Result<()> {
+        let result = &mut ctx.accounts.weighted_roll_result;
+        result.player = ctx.accounts.payer.key();
+        result.nonce = nonce;
+        result.table_id = ctx.accounts.weighted_table.table_id;
+        result.table_version = ctx.accounts.weighted_table.version;
+        result.fulfilled = false;
+        result.requested_slot = Clock::get()?.slot;
+        result.bump = ctx.bumps.weighted_roll_result;
+
+        let ix = vrf_request_ix(
+            ctx.accounts.payer.key(),
+            ctx.accounts.oracle_queue.key(),
+            instruction::CallbackRollWeighted::DISCRIMINATOR,
+            caller_seed(nonce, SEED_KIND_WEIGHTED),
+            vec![
+                callback_meta(ctx.accounts.weighted_roll_result.key(), true),
+                callback_meta(ctx.accounts.weighted_table.key(), false),
+            ],
+        );
+
+        ctx.accounts
+            .invoke_signed_vrf(&ctx.accounts.payer.to_account_info(), &ix)?;
+
+        Ok(())
+    }
+
+    /// Callback for `roll_weighted`: resolves the outcome against the table,
+    /// which must still be at the version recorded by the request.
+    pub fn callback_roll_weighted(
+        ctx: Context<CallbackRollWeightedCtx>,
+        randomness: [u8; 32],
+    ) -> Result<()> {
+        let table = &ctx.accounts.weighted_table;
+        let outcome = rarity_math::pick_weighted(&table.weights, &randomness)
+            .ok_or(VrfRarityError::InvalidWeightedTable)?;
+
+        let result = &mut ctx.accounts.weighted_roll_result;
+        result.outcome = outcome as u8;
+        result.randomness = randomness;
+        result.fulfilled = true;
+        result.fulfilled_slot = Clock::get()?.slot;
+
+        msg!(
+            "VRF weighted roll: table {} outcome {} ({})",
+            table.table_id,
+            outcome,
+            table.labels[outcome]
+        );
+
+        Ok(())
+    }
+
+    /// Cancel a weighted roll the oracle never fulfilled, including one left
+    /// unfulfillable by a table update, once `STALE_ROLL_SLOTS` have passed
+    /// since the request. Closes the PDA, refunds rent and emits
+    /// `WeightedRollCancelled`. Re-roll with a fresh nonce.
+    pub fn cancel_stale_weighted_roll(ctx: Context<CancelStaleWeightedRollCtx>) -> Result<()> {
+        let result = &ctx.accounts.weighted_roll_result;
+        let slot = Clock::get()?.slot;
+        require!(
+            slot >= result.requested_slot.saturating_add(STALE_ROLL_SLOTS),
+            VrfRarityError::RollNotStale
+        );
+
+        emit!(WeightedRollCancelled {
+            player: result.player,
+            nonce: result.nonce,
+            table_id: result.table_id,
+            requested_slot: result.requested_slot,
+            cancelled_slot: slot,
+        });
+
+        Ok(())
+    }
+
+    /// Submit a finalized game's floor price to the SOAR leaderboard. The
+    /// program's `[b"soar_authority"]` PDA is registered as the SOAR game
+    /// authority and signs the CPI, so no authority key has to live
+    /// off-chain. Each game id may be submitted once per player.
+    pub fn submit_score(ctx: Context<SubmitScoreCtx>, game_id: u64) -> Result<()> {
+        let score = ctx.accounts.game_record.summary.floor_price;
+        require!(score > 0, VrfRarityError::InvalidScore);
+
+        let submission = &mut ctx.accounts.score_submission;
+        submission.player = ctx.accounts.player.key();
+        submission.game_id = game_id;
+        submission.score = score;
+        submission.submitted_at = Clock::get()?.unix_timestamp;
+        submission.bump = ctx.bumps.score_submission;
+
+        let accounts = &ctx.accounts;
+        let ix = soar_submit_score_ix(
+            accounts.player.key(),
+            accounts.soar_authority.key(),
+            accounts.soar_player_account.key(),
+            accounts.soar_game.key(),
+            accounts.soar_leaderboard.key(),
+            accounts.soar_player_scores.key(),
+            accounts.soar_top_entries.key(),
+            score,
+        );
+        invoke_signed(
+            &ix,
+            &[
+                accounts.player.to_account_info(),
+                accounts.soar_authority.to_account_info(),
+                accounts.soar_player_account.to_account_info(),
+                accounts.soar_game.to_account_info(),
+                accounts.soar_leaderboard.to_account_info(),
+                accounts.soar_player_scores.to_account_info(),
+                accounts.soar_top_entries.to_account_info(),
+                accounts.system_program.to_account_info(),
+                accounts.soar_program.to_account_info(),
+            ],
+            &[&[SOAR_AUTHORITY_SEED, &[ctx.bumps.soar_authority]]],
+        )?;
+
+        emit!(ScoreSubmitted {
+            player: ctx.accounts.player.key(),
+            game_id,
+            score,
+        });
+
+        Ok(())
+    }
+
+    /// Record a finished game's summary in a `GameRecord` PDA. Rejects
+    /// summaries that break the game rules; leaderboards and achievements
+    /// read from the record rather than from caller-supplied numbers.
+    pub fn finalize_game(
+        ctx: Context<FinalizeGameCtx>,
+        game_id: u64,
+        summary: GameSummary,
+    ) -> Result<()> {
+        summary.validate()?;
+
+        let record = &mut ctx.accounts.game_record;
+        record.player = ctx.accounts.player.key();
+        record.game_id = game_id;
+        record.tribe = ctx.accounts.game_session.tribe;
+        record.summary = summary;
+        record.finalized_at = Clock::get()?.unix_timestamp;
+        record.bump = ctx.bumps.game_record;
+
+        emit!(GameFinalized {
+            player: record.player,
+            game_id,
+            floor_price: summary.floor_price,
+            turns_played: summary.turns_played,
+        });
+
+        Ok(())
+    }
+
+    /// Replace the allowlist of replay verifier keys. Only the config
+    /// authority may change it.
+    pub fn set_verifiers(ctx: Context<SetVerifiersCtx>, verifiers: Vec<Pubkey>) -> Result<()> {
+        require!(
+            verifiers.len() <= MAX_VERIFIERS,
+            VrfRarityError::TooManyVerifiers
+        );
+
+        let registry = &mut ctx.accounts.verifier_registry;
+        registry.verifiers = verifiers;
+        registry.bump = ctx.bumps.verifier_registry;
+        Ok(())
+    }
+
+    /// Record a score attested by an allowlisted replay verifier. The
+    /// preceding instruction must be an Ed25519 precompile check of the
+    /// verifier's signature over `(player, seed, action_log_hash, score)`.
+    pub fn attest_result(
+        ctx: Context<AttestResultCtx>,
+        game_id: u64,
+        action_log_hash: [u8; 32],
+        score: u64,
+    ) -> Result<()> {
+        let player = ctx.accounts.player.key();
+
+        let message = attestation_message(
+            &player,
+            &ctx.accounts.game_session.seed,
+            &action_log_hash,
+            score,
+        );
+
+        let instructions = ctx.accounts.instructions.to_account_info();
+        let current = load_current_index_checked(&instructions)?;
+        require!(current > 0, VrfRarityError::InvalidAttestation);
+        let ed25519_ix = load_instruction_at_checked(current as usize - 1, &instructions)?;
+        let verifier = ed25519_signer(&ed25519_ix, &message)?;
+        require!(
+            ctx.accounts.verifier_registry.verifiers.contains(&verifier),
+            VrfRarityError::VerifierNotAllowed
+        );
+
+        let attested = &mut ctx.accounts.attested_result;
+        attested.player = player;
+        attested.game_id = game_id;
+        attested.verifier = verifier;
+        attested.action_log_hash = action_log_hash;
+        attested.score = score;
+        attested.attested_at = Clock::get()?.unix_timestamp;
+        attested.bump = ctx.bumps.attested_result;
+
+        emit!(ResultAttested {
+            player,
+            game_id,
+            verifier,
+            score,
+        });
+
+        Ok(())
+    }
+
+    /// Fold a turn's action hash into the game's rolling hash,
+    /// `head = sha256(head || turn_le || action_hash)`, starting from zero.
+    /// Turns must strictly increase.
+    pub fn commit_turn(
+        ctx: Context<CommitTurnCtx>,
+        game_id: u64,
+        turn: u16,
+        action_hash: [u8; 32],
+    ) -> Result<()> {
+        let chain = &mut ctx.accounts.action_chain;
+        chain.init_if_new(ctx.accounts.player.key(), game_id, ctx.bumps.action_chain);
+        chain.commit(turn, &action_hash)?;
+
+        emit!(TurnCommitted {
+            player: chain.player,
+            game_id,
+            turn,
+            head: chain.head,
+        });
+
+        Ok(())
+    }
+
+    /// Register an achievement. Only the config authority may add them.
+    pub fn create_achievement(
+        ctx: Context<CreateAchievementCtx>,
+        achievement_id: u16,
+        slug: String,
+        condition: AchievementCondition,
+    ) -> Result<()> {
+        require!(
+            !slug.is_empty() && slug.len() <= MAX_ACHIEVEMENT_SLUG_LEN,
+            VrfRarityError::InvalidAchievement
+        );
+
+        let achievement = &mut ctx.accounts.achievement;
+        achievement.authority = ctx.accounts.authority.key();
+        achievement.achievement_id = achievement_id;
+        achievement.slug = slug;
+        achievement.condition = condition;
+        achievement.active = true;
+        achievement.bump = ctx.bumps.achievement;
+        Ok(())
+    }
+
+    /// Change an achievement's condition or retire it. Existing unlocks stay.
+    pub fn update_achievement(
+        ctx: Context<UpdateAchievementCtx>,
+        condition: AchievementCondition,
+        active: bool,
+    ) -> Result<()> {
+        let achievement = &mut ctx.accounts.achievement;
+        achievement.condition = condition;
+        achievement.active = active;
+        Ok(())
+    }
+
+    /// Unlock an achievement for the player if the stats recorded by
+    /// `finalize_game` for `game_id` meet its condition. The stats were
+    /// bounded by the game rules when the record was written. Each player
+    /// unlocks an achievement at most once.
+    pub fn claim_achievement(ctx: Context<ClaimAchievementCtx>, game_id: u64) -> Result<()> {
+        let achievement = &ctx.accounts.achievement;
+        require!(
+            achievement
+                .condition
+                .is_met(&ctx.accounts.game_record.summary),
+            VrfRarityError::AchievementNotEarned
+        );
+
+        let unlock = &mut ctx.accounts.achievement_unlock;
+        unlock.player = ctx.accounts.player.key();
+        unlock.achievement_id = achievement.achievement_id;
+        unlock.game_id = game_id;
+        unlock.unlocked_at = Clock::get()?.unix_timestamp;
+        unlock.bump = ctx.bumps.achievement_unlock;
+
+        emit!(AchievementUnlocked {
+            player: unlock.player,
+            achievement_id: unlock.achievement_id,
+            game_id,
+        });
+
+        Ok(())
+    }
+}
+
+// ---- Account Contexts ----
+
+#[vrf]
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct RollRarityCtx<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [GAME_CONTEXT_SEED, payer.key().as_ref(), &game_context.game_id.to_le_bytes()],
+        bump = game_context.bump,
+    )]
+    pub game_context: Account<'info, GameContext>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RarityResult::INIT_SPACE,
+        seeds = [RARITY_SEED, payer.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub rarity_result: Account<'info, RarityResult>,
+
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, RarityConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PlayerRollStats::INIT_SPACE,
+        seeds = [PLAYER_STATS_SEED, payer.key().as_ref()],
+        bump
+    )]
+    pub player_stats: Account<'info, PlayerRollStats>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RollLedger::INIT_SPACE,
+        seeds = [ROLL_LEDGER_SEED, payer.key().as_ref()],
+        bump
+    )]
+    pub roll_ledger: Account<'info, RollLedger>,
+
+    /// CHECK: MagicBlock oracle queue
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CallbackRollRarityCtx<'info> {
+    /// The VRF program identity PDA — proves this CPI originates from the VRF program
+    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
+    pub vrf_program_identity: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = !rarity_result.fulfilled @ VrfRarityError::AlreadyFulfilled,
+        seeds = [RARITY_SEED, rarity_result.player.as_ref(), &rarity_result.nonce.to_le_bytes()],
+        bump = rarity_result.bump,
+    )]
+    pub rarity_result: Account<'info, RarityResult>,
+
+    #[account(
+        constraint = config.version == rarity_result.config_version @ VrfRarityError::RarityConfigChanged,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, RarityConfig>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, rarity_result.player.as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Account<'info, PlayerRollStats>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRarityResultCtx<'info> {
+    /// Original requester — receives the reclaimed rent
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        close = player,
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        constraint = rarity_result.fulfilled @ VrfRarityError::RollNotFulfilled,
+        seeds = [RARITY_SEED, player.key().as_ref(), &rarity_result.nonce.to_le_bytes()],
+        bump = rarity_result.bump,
+    )]
+    pub rarity_result: Account<'info, RarityResult>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CloseLegacyRarityResultCtx<'info> {
+    /// Original requester — receives the reclaimed rent
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// CHECK: baseline-layout RarityResult, validated on its raw bytes
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [RARITY_SEED, player.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub legacy_result: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelStaleRollCtx<'info> {
+    /// Original requester — receives the reclaimed rent
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        close = player,
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        constraint = !rarity_result.fulfilled @ VrfRarityError::AlreadyFulfilled,
+        seeds = [RARITY_SEED, player.key().as_ref(), &rarity_result.nonce.to_le_bytes()],
+        bump = rarity_result.bump,
+    )]
+    pub rarity_result: Account<'info, RarityResult>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Account<'info, PlayerRollStats>,
+
+    #[account(
+        mut,
+        seeds = [ROLL_LEDGER_SEED, player.key().as_ref()],
+        bump = roll_ledger.bump,
+    )]
+    pub roll_ledger: Account<'info, RollLedger>,
+}
+
+#[derive(Accounts)]
+pub struct CancelStaleBatchCtx<'info> {
+    /// Original requester — receives the reclaimed rent
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        close = player,
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        constraint = !batch_result.fulfilled @ VrfRarityError::AlreadyFulfilled,
+        seeds = [RARITY_BATCH_SEED, player.key().as_ref(), &batch_result.nonce.to_le_bytes()],
+        bump = batch_result.bump,
+    )]
+    pub batch_result: Account<'info, RarityBatchResult>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, player.key().as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Account<'info, PlayerRollStats>,
+
+    #[account(
+        mut,
+        seeds = [ROLL_LEDGER_SEED, player.key().as_ref()],
+        bump = roll_ledger.bump,
+    )]
+    pub roll_ledger: Account<'info, RollLedger>,
+}
+
+#[vrf]
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct RollRarityBatchCtx<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [GAME_CONTEXT_SEED, payer.key().as_ref(), &game_context.game_id.to_le_bytes()],
+        bump = game_context.bump,
+    )]
+    pub game_context: Account<'info, GameContext>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RarityBatchResult::INIT_SPACE,
+        seeds = [RARITY_BATCH_SEED, payer.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub batch_result: Account<'info, RarityBatchResult>,
+
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, RarityConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PlayerRollStats::INIT_SPACE,
+        seeds = [PLAYER_STATS_SEED, payer.key().as_ref()],
+        bump
+    )]
+    pub player_stats: Account<'info, PlayerRollStats>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + RollLedger::INIT_SPACE,
+        seeds = [ROLL_LEDGER_SEED, payer.key().as_ref()],
+        bump
+    )]
+    pub roll_ledger: Account<'info, RollLedger>,
+
+    /// CHECK: MagicBlock oracle queue
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CallbackRollRarityBatchCtx<'info> {
+    /// The VRF program identity PDA — proves this CPI originates from the VRF program
+    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
+    pub vrf_program_identity: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = !batch_result.fulfilled @ VrfRarityError::AlreadyFulfilled,
+        seeds = [RARITY_BATCH_SEED, batch_result.player.as_ref(), &batch_result.nonce.to_le_bytes()],
+        bump = batch_result.bump,
+    )]
+    pub batch_result: Account<'info, RarityBatchResult>,
+
+    #[account(
+        constraint = config.version == batch_result.config_version @ VrfRarityError::RarityConfigChanged,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, RarityConfig>,
+
+    #[account(
+        mut,
+        seeds = [PLAYER_STATS_SEED, batch_result.player.as_ref()],
+        bump = player_stats.bump,
+    )]
+    pub player_stats: Account<'info, PlayerRollStats>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRarityBatchCtx<'info> {
+    /// Original requester — receives the reclaimed rent
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        close = player,
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        constraint = batch_result.fulfilled @ VrfRarityError::RollNotFulfilled,
+        seeds = [RARITY_BATCH_SEED, player.key().as_ref(), &batch_result.nonce.to_le_bytes()],
+        bump = batch_result.bump,
+    )]
+    pub batch_result: Account<'info, RarityBatchResult>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfigCtx<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RarityConfig::INIT_SPACE,
+        seeds = [CONFIG_SEED],
+        bump
+    )]
+    pub config: Account<'info, RarityConfig>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::VrfRarity>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ VrfRarityError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfigCtx<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ VrfRarityError::Unauthorized,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, RarityConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(unit_class: UnitClass)]
+pub struct UpdateRarityTableCtx<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ VrfRarityError::Unauthorized,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, RarityConfig>,
+
+    /// The class's rarity odds as a `WeightedTable`, rewritten alongside
+    #[account(
+        mut,
+        seeds = [WEIGHTED_TABLE_SEED, &unit_class.weighted_table_id().to_le_bytes()],
+        bump = weighted_table.bump,
+    )]
+    pub weighted_table: Account<'info, WeightedTable>,
+}
+
+#[derive(Accounts)]
+#[instruction(unit_class: UnitClass)]
+pub struct RegisterRarityTableCtx<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        has_one = authority @ VrfRarityError::Unauthorized,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, RarityConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + WeightedTable::INIT_SPACE,
+        seeds = [WEIGHTED_TABLE_SEED, &unit_class.weighted_table_id().to_le_bytes()],
+        bump
+    )]
+    pub weighted_table: Account<'info, WeightedTable>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[vrf]
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct StartGameCtx<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + GameSession::INIT_SPACE,
+        seeds = [GAME_SESSION_SEED, payer.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    /// CHECK: MagicBlock oracle queue
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CallbackStartGameCtx<'info> {
+    /// The VRF program identity PDA — proves this CPI originates from the VRF program
+    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
+    pub vrf_program_identity: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = !game_session.fulfilled @ VrfRarityError::AlreadyFulfilled,
+        seeds = [GAME_SESSION_SEED, game_session.player.as_ref(), &game_session.game_id.to_le_bytes()],
+        bump = game_session.bump,
+    )]
+    pub game_session: Account<'info, GameSession>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct OpenGameContextCtx<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        init,
+        payer = player,
+        space = 8 + GameContext::INIT_SPACE,
+        seeds = [GAME_CONTEXT_SEED, player.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub game_context: Account<'info, GameContext>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGameContextCtx<'info> {
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        seeds = [GAME_CONTEXT_SEED, player.key().as_ref(), &game_context.game_id.to_le_bytes()],
+        bump = game_context.bump,
+    )]
+    pub game_context: Account<'info, GameContext>,
+
+    #[account(
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        seeds = [ACTION_CHAIN_SEED, player.key().as_ref(), &game_context.game_id.to_le_bytes()],
+        bump = action_chain.bump,
+    )]
+    pub action_chain: Account<'info, ActionChain>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGameContextCtx<'info> {
+    /// Original requester — receives the reclaimed rent
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        close = player,
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        seeds = [GAME_CONTEXT_SEED, player.key().as_ref(), &game_context.game_id.to_le_bytes()],
+        bump = game_context.bump,
+    )]
+    pub game_context: Account<'info, GameContext>,
+}
+
+#[derive(Accounts)]
+pub struct ConsumeRollCtx<'info> {
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        constraint = rarity_result.fulfilled @ VrfRarityError::RollNotFulfilled,
+        constraint = !rarity_result.consumed @ VrfRarityError::RollAlreadyConsumed,
+        seeds = [RARITY_SEED, player.key().as_ref(), &rarity_result.nonce.to_le_bytes()],
+        bump = rarity_result.bump,
+    )]
+    pub rarity_result: Account<'info, RarityResult>,
+
+    #[account(
+        mut,
+        seeds = [ROLL_LEDGER_SEED, player.key().as_ref()],
+        bump = roll_ledger.bump,
+    )]
+    pub roll_ledger: Account<'info, RollLedger>,
+}
+
+#[derive(Accounts)]
+pub struct ConsumeBatchRollCtx<'info> {
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        constraint = batch_result.fulfilled @ VrfRarityError::RollNotFulfilled,
+        seeds = [RARITY_BATCH_SEED, player.key().as_ref(), &batch_result.nonce.to_le_bytes()],
+        bump = batch_result.bump,
+    )]
+    pub batch_result: Account<'info, RarityBatchResult>,
+
+    #[account(
+        mut,
+        seeds = [ROLL_LEDGER_SEED, player.key().as_ref()],
+        bump = roll_ledger.bump,
+    )]
+    pub roll_ledger: Account<'info, RollLedger>,
+}
+
+#[vrf]
+#[derive(Accounts)]
+#[instruction(lootbox_id: u32)]
+pub struct RollLootboxCtx<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = game_session.player == payer.key() @ VrfRarityError::PlayerMismatch,
+        constraint = game_session.fulfilled @ VrfRarityError::RollNotFulfilled,
+        seeds = [GAME_SESSION_SEED, payer.key().as_ref(), &game_session.game_id.to_le_bytes()],
+        bump = game_session.bump,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + LootboxResult::INIT_SPACE,
+        seeds = [LOOTBOX_SEED, game_session.key().as_ref(), &lootbox_id.to_le_bytes()],
+        bump
+    )]
+    pub lootbox_result: Account<'info, LootboxResult>,
+
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, RarityConfig>,
+
+    /// CHECK: MagicBlock oracle queue
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CallbackRollLootboxCtx<'info> {
+    /// The VRF program identity PDA — proves this CPI originates from the VRF program
+    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
+    pub vrf_program_identity: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = !lootbox_result.fulfilled @ VrfRarityError::AlreadyFulfilled,
+        seeds = [LOOTBOX_SEED, lootbox_result.game_session.as_ref(), &lootbox_result.lootbox_id.to_le_bytes()],
+        bump = lootbox_result.bump,
+    )]
+    pub lootbox_result: Account<'info, LootboxResult>,
+
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, RarityConfig>,
+}
+
+#[vrf]
+#[derive(Accounts)]
+pub struct RollGoldenAgeEffectCtx<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        constraint = game_session.fulfilled @ VrfRarityError::RollNotFulfilled,
+        seeds = [GAME_SESSION_SEED, payer.key().as_ref(), &game_context.game_id.to_le_bytes()],
+        bump = game_session.bump,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(
+        seeds = [GAME_CONTEXT_SEED, payer.key().as_ref(), &game_context.game_id.to_le_bytes()],
+        bump = game_context.bump,
+    )]
+    pub game_context: Account<'info, GameContext>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + GoldenAgeResult::INIT_SPACE,
+        seeds = [GOLDEN_AGE_SEED, game_session.key().as_ref(), &game_context.current_turn.to_le_bytes()],
+        bump
+    )]
+    pub golden_age_result: Account<'info, GoldenAgeResult>,
+
+    /// CHECK: MagicBlock oracle queue
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CallbackRollGoldenAgeEffectCtx<'info> {
+    /// The VRF program identity PDA — proves this CPI originates from the VRF program
+    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
+    pub vrf_program_identity: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = !golden_age_result.fulfilled @ VrfRarityError::AlreadyFulfilled,
+        seeds = [GOLDEN_AGE_SEED, golden_age_result.game_session.as_ref(), &golden_age_result.turn.to_le_bytes()],
+        bump = golden_age_result.bump,
+    )]
+    pub golden_age_result: Account<'info, GoldenAgeResult>,
+}
+
+#[vrf]
+#[derive(Accounts)]
+#[instruction(tag: u32, turn: u16)]
+pub struct RollProbabilityCheckCtx<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        constraint = game_session.fulfilled @ VrfRarityError::RollNotFulfilled,
+        seeds = [GAME_SESSION_SEED, payer.key().as_ref(), &game_context.game_id.to_le_bytes()],
+        bump = game_session.bump,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(
+        seeds = [GAME_CONTEXT_SEED, payer.key().as_ref(), &game_context.game_id.to_le_bytes()],
+        bump = game_context.bump,
+    )]
+    pub game_context: Account<'info, GameContext>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ProbabilityCheck::INIT_SPACE,
+        seeds = [PROBABILITY_CHECK_SEED, game_session.key().as_ref(), &tag.to_le_bytes(), &turn.to_le_bytes()],
+        bump
+    )]
+    pub probability_check: Account<'info, ProbabilityCheck>,
+
+    /// CHECK: MagicBlock oracle queue
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CallbackProbabilityCheckCtx<'info> {
+    /// The VRF program identity PDA — proves this CPI originates from the VRF program
+    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
+    pub vrf_program_identity: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = !probability_check.fulfilled @ VrfRarityError::AlreadyFulfilled,
+        seeds = [
+            PROBABILITY_CHECK_SEED,
+            probability_check.game_session.as_ref(),
+            &probability_check.tag.to_le_bytes(),
+            &probability_check.turn.to_le_bytes(),
+        ],
+        bump = probability_check.bump,
+    )]
+    pub probability_check: Account<'info, ProbabilityCheck>,
+}
+
+#[derive(Accounts)]
+pub struct MintUnitCollectibleCtx<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        constraint = rarity_result.player == payer.key() @ VrfRarityError::PlayerMismatch,
+        constraint = rarity_result.fulfilled @ VrfRarityError::RollNotFulfilled,
+        constraint = rarity_result.rarity >= COLLECTIBLE_MIN_RARITY @ VrfRarityError::RarityTooLow,
+        seeds = [RARITY_SEED, payer.key().as_ref(), &rarity_result.nonce.to_le_bytes()],
+        bump = rarity_result.bump,
+    )]
+    pub rarity_result: Account<'info, RarityResult>,
+
+    /// Supplies the tribe recorded in the collectible's metadata
+    #[account(
+        seeds = [GAME_SESSION_SEED, payer.key().as_ref(), &rarity_result.game_id.to_le_bytes()],
+        bump = game_session.bump,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [COLLECTIBLE_SEED, rarity_result.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = collectible_mint,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = collectible_mint,
+        extensions::metadata_pointer::metadata_address = collectible_mint,
+    )]
+    pub collectible_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = collectible_mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program,
+    )]
+    pub player_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(table_id: u32)]
+pub struct CreateWeightedTableCtx<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        has_one = authority @ VrfRarityError::Unauthorized,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, RarityConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + WeightedTable::INIT_SPACE,
+        seeds = [WEIGHTED_TABLE_SEED, &table_id.to_le_bytes()],
+        bump
+    )]
+    pub weighted_table: Account<'info, WeightedTable>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateWeightedTableCtx<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ VrfRarityError::Unauthorized,
+        seeds = [WEIGHTED_TABLE_SEED, &weighted_table.table_id.to_le_bytes()],
+        bump = weighted_table.bump,
+    )]
+    pub weighted_table: Account<'info, WeightedTable>,
+}
+
+#[vrf]
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct RollWeightedCtx<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [WEIGHTED_TABLE_SEED, &weighted_table.table_id.to_le_bytes()],
+        bump = weighted_table.bump,
+    )]
+    pub weighted_table: Account<'info, WeightedTable>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + WeightedRollResult::INIT_SPACE,
+        seeds = [WEIGHTED_ROLL_SEED, payer.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub weighted_roll_result: Account<'info, WeightedRollResult>,
+
+    /// CHECK: MagicBlock oracle queue
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CallbackRollWeightedCtx<'info> {
+    /// The VRF program identity PDA — proves this CPI originates from the VRF program
+    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
+    pub vrf_program_identity: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = !weighted_roll_result.fulfilled @ VrfRarityError::AlreadyFulfilled,
+        seeds = [WEIGHTED_ROLL_SEED, weighted_roll_result.player.as_ref(), &weighted_roll_result.nonce.to_le_bytes()],
+        bump = weighted_roll_result.bump,
+    )]
+    pub weighted_roll_result: Account<'info, WeightedRollResult>,
+
+    #[account(
+        constraint = weighted_table.version == weighted_roll_result.table_version @ VrfRarityError::WeightedTableChanged,
+        seeds = [WEIGHTED_TABLE_SEED, &weighted_roll_result.table_id.to_le_bytes()],
+        bump = weighted_table.bump,
+    )]
+    pub weighted_table: Account<'info, WeightedTable>,
+}
+
+#[derive(Accounts)]
+pub struct CancelStaleWeightedRollCtx<'info> {
+    /// Original requester — receives the reclaimed rent
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        close = player,
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        constraint = !weighted_roll_result.fulfilled @ VrfRarityError::AlreadyFulfilled,
+        seeds = [WEIGHTED_ROLL_SEED, player.key().as_ref(), &weighted_roll_result.nonce.to_le_bytes()],
+        bump = weighted_roll_result.bump,
+    )]
+    pub weighted_roll_result: Account<'info, WeightedRollResult>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct SubmitScoreCtx<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// One per player and game id; `init` rejects a second submission
+    #[account(
+        init,
+        payer = player,
+        space = 8 + ScoreSubmission::INIT_SPACE,
+        seeds = [SCORE_SUBMISSION_SEED, player.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub score_submission: Account<'info, ScoreSubmission>,
+
+    /// The validated record the submitted score is read from
+    #[account(
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        seeds = [GAME_RECORD_SEED, player.key().as_ref(), &game_id.to_le_bytes()],
+        bump = game_record.bump,
+    )]
+    pub game_record: Account<'info, GameRecord>,
+
+    /// CHECK: PDA registered as a SOAR game authority, signs the CPI
+    #[account(seeds = [SOAR_AUTHORITY_SEED], bump)]
+    pub soar_authority: UncheckedAccount<'info>,
+
+    /// CHECK: `player`'s SOAR profile; the seeds pin it to the signer
+    #[account(
+        seeds = [SOAR_PLAYER_SEED, player.key().as_ref()],
+        bump,
+        seeds::program = SOAR_PROGRAM_ID,
+    )]
+    pub soar_player_account: UncheckedAccount<'info>,
+
+    /// CHECK: SOAR game, validated by SOAR against `soar_authority`
+    pub soar_game: UncheckedAccount<'info>,
+
+    /// CHECK: SOAR leaderboard, validated by SOAR against `soar_game`
+    pub soar_leaderboard: UncheckedAccount<'info>,
+
+    /// CHECK: score list of `soar_player_account` on `soar_leaderboard`
+    #[account(
+        mut,
+        seeds = [
+            SOAR_PLAYER_SCORES_SEED,
+            soar_player_account.key().as_ref(),
+            soar_leaderboard.key().as_ref(),
+        ],
+        bump,
+        seeds::program = SOAR_PROGRAM_ID,
+    )]
+    pub soar_player_scores: UncheckedAccount<'info>,
+
+    /// CHECK: SOAR leaderboard top entries, validated by SOAR
+    #[account(mut)]
+    pub soar_top_entries: UncheckedAccount<'info>,
+
+    /// CHECK: SOAR program
+    #[account(address = SOAR_PROGRAM_ID)]
+    pub soar_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct FinalizeGameCtx<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        constraint = game_session.fulfilled @ VrfRarityError::RollNotFulfilled,
+        seeds = [GAME_SESSION_SEED, player.key().as_ref(), &game_id.to_le_bytes()],
+        bump = game_session.bump,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(
+        init,
+        payer = player,
+        space = 8 + GameRecord::INIT_SPACE,
+        seeds = [GAME_RECORD_SEED, player.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub game_record: Account<'info, GameRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetVerifiersCtx<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        has_one = authority @ VrfRarityError::Unauthorized,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, RarityConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + VerifierRegistry::INIT_SPACE,
+        seeds = [VERIFIER_REGISTRY_SEED],
+        bump
+    )]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct AttestResultCtx<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// Supplies the seed the verifier replayed the game from
+    #[account(
+        constraint = game_session.fulfilled @ VrfRarityError::RollNotFulfilled,
+        seeds = [GAME_SESSION_SEED, player.key().as_ref(), &game_id.to_le_bytes()],
+        bump = game_session.bump,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(seeds = [VERIFIER_REGISTRY_SEED], bump = verifier_registry.bump)]
+    pub verifier_registry: Account<'info, VerifierRegistry>,
+
+    #[account(
+        init,
+        payer = player,
+        space = 8 + AttestedResult::INIT_SPACE,
+        seeds = [ATTESTED_RESULT_SEED, player.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub attested_result: Account<'info, AttestedResult>,
+
+    /// CHECK: instructions sysvar, read to find the Ed25519 precompile check
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct CommitTurnCtx<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + ActionChain::INIT_SPACE,
+        seeds = [ACTION_CHAIN_SEED, player.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub action_chain: Account<'info, ActionChain>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(achievement_id: u16)]
+pub struct CreateAchievementCtx<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        has_one = authority @ VrfRarityError::Unauthorized,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, RarityConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AchievementDefinition::INIT_SPACE,
+        seeds = [ACHIEVEMENT_SEED, &achievement_id.to_le_bytes()],
+        bump
+    )]
+    pub achievement: Account<'info, AchievementDefinition>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAchievementCtx<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ VrfRarityError::Unauthorized,
+        seeds = [ACHIEVEMENT_SEED, &achievement.achievement_id.to_le_bytes()],
+        bump = achievement.bump,
+    )]
+    pub achievement: Account<'info, AchievementDefinition>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct ClaimAchievementCtx<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        constraint = achievement.active @ VrfRarityError::AchievementInactive,
+        seeds = [ACHIEVEMENT_SEED, &achievement.achievement_id.to_le_bytes()],
+        bump = achievement.bump,
+    )]
+    pub achievement: Account<'info, AchievementDefinition>,
+
+    #[account(
+        has_one = player @ VrfRarityError::PlayerMismatch,
+        seeds = [GAME_RECORD_SEED, player.key().as_ref(), &game_id.to_le_bytes()],
+        bump = game_record.bump,
+    )]
+    pub game_record: Account<'info, GameRecord>,
+
+    #[account(
+        init,
+        payer = player,
+        space = 8 + AchievementUnlock::INIT_SPACE,
+        seeds = [ACHIEVEMENT_UNLOCK_SEED, achievement.key().as_ref(), player.key().as_ref()],
+        bump
+    )]
+    pub achievement_unlock: Account<'info, AchievementUnlock>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ---- State ----
+
+#[account]
+#[derive(InitSpace)]
+pub struct RarityResult {
+    pub version: u8,           //  1 — account layout version (RARITY_RESULT_VERSION)
+    pub player: Pubkey,        // 32 — wallet that requested the roll
+    pub nonce: u64,            //  8 — unique roll identifier
+    pub rarity: u8,            //  1 — 0=Common 1=Uncommon 2=Rare 3=Epic 4=Legendary
+    pub fulfilled: bool,       //  1 — true after VRF callback
+    pub roll_value: u16,       //  2 — raw random value in basis points [0, 9999]
+    pub bump: u8,              //  1 — PDA bump seed
+    pub config_version: u32,   //  4 — RarityConfig version pinned at request
+    pub unit_class: UnitClass, //  1 — table the roll was resolved against
+    pub randomness: [u8; 32],  // 32 — raw VRF output the roll was derived from
+    pub requested_slot: u64,   //  8 — slot of the roll_rarity request
+    pub fulfilled_slot: u64,   //  8 — slot of the oracle callback
+    pub fulfilled_at: i64,     //  8 — unix timestamp of the oracle callback
+    pub pity_applied: bool,    //  1 — true if bad-luck protection raised the tier
+    pub game_id: u64,          //  8 — GameContext the roll was made in
+    pub turn: u16,             //  2 — game turn the roll was made on
+    pub settlement_id: u32,    //  4 — numeric suffix of the minting SettlementId
+    pub unit_type: u8,         //  1 — index of the unit's UnitType in game-core
+    pub consumed: bool,        //  1 — true once applied to a unit via consume_roll
+    pub consumed_unit_id: u32, //  4 — numeric suffix of the UnitId the roll was applied to
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct RarityRoll {
+    pub rarity: u8,         // 1 — tier index into RarityConfig
+    pub roll_value: u16,    // 2 — raw random value in basis points [0, 9999]
+    pub pity_applied: bool, // 1 — true if bad-luck protection raised the tier
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct RarityBatchResult {
+    pub player: Pubkey,                       // 32 — wallet that requested the rolls
+    pub nonce: u64,                           //  8 — unique batch identifier
+    pub count: u8,                            //  1 — number of slots in use
+    pub fulfilled: bool,                      //  1 — true after VRF callback
+    pub config_version: u32,                  //  4 — RarityConfig version pinned at request
+    pub rolls: [RarityRoll; MAX_BATCH_ROLLS], // 40 — per-slot results, first `count` valid
+    pub bump: u8,                             //  1 — PDA bump seed
+    pub unit_class: UnitClass,                //  1 — table every slot was resolved against
+    pub game_id: u64,                         //  8 — GameContext the batch was made in
+    pub turn: u16,                            //  2 — game turn the batch was made on
+    pub consumed_count: u8,                   //  1 — slots applied to units so far
+    pub unit_ids: [u32; MAX_BATCH_ROLLS],     // 40 — UnitId suffix each consumed slot went to
+    pub requested_slot: u64,                  //  8 — slot of the roll_rarity_batch request
+}
+
+impl RarityBatchResult {
+    /// Mark `slot_index` as used for `unit_id`. Slots are consumed in order;
+    /// slot 0 consumes the batch's nonce in `ledger`. Returns how many earlier
+    /// nonces that skipped.
+    pub fn consume_slot(
+        &mut self,
+        slot_index: u8,
+        unit_id: u32,
+        ledger: &mut RollLedger,
+    ) -> Result<u64> {
+        require!(
+            slot_index == self.consumed_count && slot_index < self.count,
+            VrfRarityError::InvalidBatchSlot
+        );
+
+        let skipped = if slot_index == 0 {
+            ledger.consume(self.nonce)?
+        } else {
+            0
+        };
+        self.unit_ids[slot_index as usize] = unit_id;
+        self.consumed_count += 1;
+        Ok(skipped)
+    }
+}
+
+/// Unit class a rarity roll is made for; each class has its own odds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, InitSpace)]
+pub enum UnitClass {
+    #[default]
+    Melee,
+    Ranged,
+    Cavalry,
+    Siege,
+    Unique,
+}
+
+impl UnitClass {
+    /// Id of the `WeightedTable` holding this class's rarity odds.
+    pub fn weighted_table_id(self) -> u32 {
+        self as u32
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct RarityTable {
+    pub tier_count: u8,                      //  1 — number of tiers in use
+    pub thresholds: [u16; MAX_RARITY_TIERS], // 16 — inclusive upper roll bound per tier (bps)
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RarityConfig {
+    pub authority: Pubkey,                       // 32 — may update the tables
+    pub version: u32,                            //  4 — bumped on every update
+    pub tables: [RarityTable; UNIT_CLASS_COUNT], // 85 — one table per UnitClass
+    pub bump: u8,                                //  1 — PDA bump seed
+    pub pity_threshold: u8,                      //  1 — misses before a forced Rare, 0 = off
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct PlayerRollStats {
+    pub player: Pubkey,          // 32 — wallet these stats belong to
+    pub consecutive_misses: u16, //  2 — sub-Rare rolls since the last Rare or better
+    pub total_rolls: u64,        //  8 — rolls fulfilled for this player
+    pub pity_triggers: u32,      //  4 — times bad-luck protection kicked in
+    pub bump: u8,                //  1 — PDA bump seed
+    pub cancelled_rolls: u32,    //  4 — stale rolls cancelled via cancel_stale_roll/_batch
+}
+
+impl PlayerRollStats {
+    /// Claim a freshly created stats account for `player`.
+    pub fn init_if_new(&mut self, player: Pubkey, bump: u8) {
+        if self.player == Pubkey::default() {
+            self.player = player;
+            self.bump = bump;
+        }
+    }
+
+    /// Apply bad-luck protection to a rolled tier and update the streak.
+    /// With `pity_threshold` misses already in a row, the roll is raised to
+    /// Rare (or the table's top tier if it has fewer). Returns the final tier
+    /// and whether protection raised it.
+    pub fn apply_roll(
+        &mut self,
+        rarity: u8,
+        pity_threshold: u8,
+        table: &RarityTable,
+    ) -> (u8, bool) {
+        let pity_tier = PITY_MIN_RARITY.min(table.tier_count.saturating_sub(1));
+        let pity_applied = pity_threshold > 0
+            && rarity < pity_tier
+            && self.consecutive_misses >= pity_threshold as u16;
+        let rarity = if pity_applied { pity_tier } else { rarity };
+
+        if rarity >= pity_tier {
+            self.consecutive_misses = 0;
+        } else {
+            self.consecutive_misses = self.consecutive_misses.saturating_add(1);
+        }
+        self.total_rolls = self.total_rolls.saturating_add(1);
+        if pity_applied {
+            self.pity_triggers = self.pity_triggers.saturating_add(1);
+        }
+
+        (rarity, pity_applied)
+    }
+}
+
+impl RarityConfig {
+    pub fn table(&self, unit_class: UnitClass) -> &RarityTable {
+        &self.tables[unit_class as usize]
+    }
+}
+
+impl RarityTable {
+    /// Validate a new table. Bounds must be strictly increasing and the last
+    /// one must be `ROLL_MAX` so every roll maps to a tier.
+    pub fn new(thresholds: &[u16]) -> Result<Self> {
+        require!(
+            thresholds.len() <= MAX_RARITY_TIERS && rarity_math::is_valid_table(thresholds),
+            VrfRarityError::InvalidRarityTable
+        );
+
+        let mut table = Self {
+            tier_count: thresholds.len() as u8,
+            thresholds: [ROLL_MAX; MAX_RARITY_TIERS],
+        };
+        table.thresholds[..thresholds.len()].copy_from_slice(thresholds);
+        Ok(table)
+    }
+
+    /// Map a roll in [0, ROLL_MAX] to its tier index.
+    pub fn tier_for_roll(&self, roll: u16) -> u8 {
+        rarity_math::tier_for_roll(&self.thresholds[..self.tier_count as usize], roll)
+    }
+}
+
+/// Playable tribes, mirrors `TribeName` in game-core.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, InitSpace)]
+pub enum Tribe {
+    #[default]
+    Monkes,
+    Geckos,
+    Degods,
+    Cets,
+    Gregs,
+    Dragonz,
+}
+
+impl Tribe {
+    /// game-core's `TribeName` string for this tribe.
+    pub fn name(self) -> &'static str {
+        match self {
+            Tribe::Monkes => "monkes",
+            Tribe::Geckos => "geckos",
+            Tribe::Degods => "degods",
+            Tribe::Cets => "cets",
+            Tribe::Gregs => "gregs",
+            Tribe::Dragonz => "dragonz",
+        }
+    }
+}
+
+/// A ranked game's provably random setup. The app derives the numeric
+/// `GameState.seed` from the first four bytes of `seed` (little-endian u32).
+#[account]
+#[derive(InitSpace, Default)]
+pub struct GameSession {
+    pub player: Pubkey,       // 32 — wallet that started the game
+    pub game_id: u64,         //  8 — player-chosen game identifier
+    pub tribe: Tribe,         //  1 — tribe the player is playing
+    pub map_width: u8,        //  1 — map width in hexes
+    pub map_height: u8,       //  1 — map height in hexes
+    pub player_count: u8,     //  1 — tribes in the game, including AI
+    pub seed: [u8; 32],       // 32 — VRF map seed, valid once fulfilled
+    pub fulfilled: bool,      //  1 — true after VRF callback
+    pub requested_slot: u64,  //  8 — slot of the start_game request
+    pub fulfilled_slot: u64,  //  8 — slot of the oracle callback
+    pub bump: u8,             //  1 — PDA bump seed
+    pub lootboxes_opened: u8, //  1 — lootboxes rolled so far, at most MAX_LOOTBOXES
+}
+
+impl GameSession {
+    /// Count a lootbox roll against the session's `MAX_LOOTBOXES`.
+    pub fn open_lootbox(&mut self, lootbox_id: u32) -> Result<()> {
+        require!(
+            (1..=MAX_LOOTBOXES as u32).contains(&lootbox_id)
+                && self.lootboxes_opened < MAX_LOOTBOXES,
+            VrfRarityError::InvalidLootbox
+        );
+        self.lootboxes_opened += 1;
+        Ok(())
+    }
+}
+
+/// Ties rarity rolls to one game and its current turn.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct GameContext {
+    pub player: Pubkey,    // 32 — wallet playing the game
+    pub game_id: u64,      //  8 — player-chosen game identifier
+    pub current_turn: u16, //  2 — turn rolls are currently allowed for
+    pub bump: u8,          //  1 — PDA bump seed
+    pub era: u8,           //  1 — player's tech era, reported with each turn advance
+}
+
+impl GameContext {
+    /// Check that a roll request matches this context.
+    pub fn check_roll(&self, player: Pubkey, turn: u16) -> Result<()> {
+        require_keys_eq!(self.player, player, VrfRarityError::PlayerMismatch);
+        require!(turn == self.current_turn, VrfRarityError::InvalidTurn);
+        Ok(())
+    }
+
+    /// Move to `turn`, which must directly follow the current turn, in
+    /// `era`. `committed_turn` is the game's last `ActionChain` turn and must
+    /// be the current one. Eras never go back.
+    pub fn advance(&mut self, turn: u16, era: u8, committed_turn: u16) -> Result<()> {
+        require!(
+            committed_turn == self.current_turn,
+            VrfRarityError::TurnNotCommitted
+        );
+        require!(
+            turn == self.current_turn + 1 && turn <= MAX_TURNS,
+            VrfRarityError::InvalidTurn
+        );
+        require!(
+            era >= self.era && golden_age_effect_count(era).is_some(),
+            VrfRarityError::InvalidEra
+        );
+        self.current_turn = turn;
+        self.era = era;
+        Ok(())
+    }
+}
+
+/// Per-player roll sequence. Nonces are handed out in order and must be
+/// consumed in order, so any roll a player discarded shows up as skipped.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct RollLedger {
+    pub player: Pubkey,          // 32 — wallet this ledger belongs to
+    pub next_nonce: u64,         //  8 — nonce the next roll or batch must use
+    pub next_consume_nonce: u64, //  8 — lowest nonce not yet consumed, cancelled or skipped
+    pub rolls_consumed: u64,     //  8 — rolls and batches applied to a unit
+    pub rolls_skipped: u64,      //  8 — nonces passed over without being consumed
+    pub bump: u8,                //  1 — PDA bump seed
+}
+
+impl RollLedger {
+    /// Claim a freshly created ledger for `player`.
+    pub fn init_if_new(&mut self, player: Pubkey, bump: u8) {
+        if self.player == Pubkey::default() {
+            self.player = player;
+            self.bump = bump;
+        }
+    }
+
+    /// Take the next sequential nonce; `nonce` must match it.
+    pub fn assign(&mut self, nonce: u64) -> Result<()> {
+        require!(nonce == self.next_nonce, VrfRarityError::InvalidNonce);
+        self.next_nonce += 1;
+        Ok(())
+    }
+
+    /// Record `nonce` as consumed. Returns how many earlier nonces this skips.
+    pub fn consume(&mut self, nonce: u64) -> Result<u64> {
+        require!(
+            nonce >= self.next_consume_nonce,
+            VrfRarityError::RollAlreadyConsumed
+        );
+        self.rolls_consumed = self.rolls_consumed.saturating_add(1);
+        Ok(self.pass(nonce))
+    }
+
+    /// Record `nonce` as cancelled (counted in `PlayerRollStats`), so it is
+    /// not taken for a skip. A nonce a later consume already passed over was
+    /// counted as skipped and is taken back out. Returns how many earlier
+    /// nonces this skips.
+    pub fn cancel(&mut self, nonce: u64) -> u64 {
+        if nonce < self.next_consume_nonce {
+            self.rolls_skipped = self.rolls_skipped.saturating_sub(1);
+            return 0;
+        }
+        self.pass(nonce)
+    }
+
+    /// Move past `nonce`, counting the nonces before it as skipped.
+    fn pass(&mut self, nonce: u64) -> u64 {
+        let skipped = nonce - self.next_consume_nonce;
+        self.rolls_skipped = self.rolls_skipped.saturating_add(skipped);
+        self.next_consume_nonce = nonce + 1;
+        skipped
+    }
+}
+
+/// Lootbox reward categories, mirrors `LootboxReward` in game-core.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, InitSpace)]
+pub enum LootboxReward {
+    #[default]
+    Airdrop,
+    AlphaLeak,
+    OgHolder,
+    CommunityGrowth,
+    Scout,
+}
+
+impl LootboxReward {
+    /// Map an index into `LOOTBOX_REWARD_WEIGHTS` to its reward.
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            1 => LootboxReward::AlphaLeak,
+            2 => LootboxReward::OgHolder,
+            3 => LootboxReward::CommunityGrowth,
+            4 => LootboxReward::Scout,
+            _ => LootboxReward::Airdrop,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LootboxResult {
+    pub player: Pubkey,        // 32 — wallet that claimed the lootbox
+    pub game_session: Pubkey,  // 32 — GameSession the lootbox belongs to
+    pub lootbox_id: u32,       //  4 — numeric suffix of the LootboxId
+    pub fulfilled: bool,       //  1 — true after VRF callback
+    pub reward: LootboxReward, //  1 — reward category
+    pub gold_amount: u8,       //  1 — airdrop gold, also the fallback when no settlement exists
+    pub unit_rarity: u8,       //  1 — OG Holder unit's rarity tier
+    pub unit_roll_value: u16,  //  2 — OG Holder unit's raw roll in basis points
+    pub randomness: [u8; 32],  // 32 — raw VRF output
+    pub bump: u8,              //  1 — PDA bump seed
+}
+
+/// A golden age effect pick. One per game session and turn.
+#[account]
+#[derive(InitSpace)]
+pub struct GoldenAgeResult {
+    pub player: Pubkey,       // 32 — wallet whose tribe entered the golden age
+    pub game_session: Pubkey, // 32 — GameSession the golden age belongs to
+    pub turn: u16,            //  2 — turn the golden age started on
+    pub era: u8,              //  1 — player's era (1-3) when it started
+    pub effect_count: u8,     //  1 — effects available in that era
+    pub effect_index: u8,     //  1 — index into the era's effect list
+    pub fulfilled: bool,      //  1 — true after VRF callback
+    pub randomness: [u8; 32], // 32 — raw VRF output
+    pub bump: u8,             //  1 — PDA bump seed
+}
+
+/// Outcome of a `numerator / denominator` chance check, keyed by game
+/// session, tag and turn.
+#[account]
+#[derive(InitSpace)]
+pub struct ProbabilityCheck {
+    pub player: Pubkey,       // 32 — wallet that requested the check
+    pub game_session: Pubkey, // 32 — GameSession the check belongs to
+    pub tag: u32,             //  4 — caller-defined subject, e.g. great person index
+    pub turn: u16,            //  2 — turn the check was made on
+    pub numerator: u32,       //  4 — pass chance numerator
+    pub denominator: u32,     //  4 — pass chance denominator
+    pub passed: bool,         //  1 — check result, valid once fulfilled
+    pub fulfilled: bool,      //  1 — true after VRF callback
+    pub randomness: [u8; 32], // 32 — raw VRF output
+    pub bump: u8,             //  1 — PDA bump seed
+}
+
+/// A labelled set of outcome weights that `roll_weighted` picks from.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct WeightedTable {
+    pub authority: Pubkey, //  32 — may update the table
+    pub table_id: u32,     //   4 — PDA seed
+    pub version: u32,      //   4 — bumped on every update
+    #[max_len(MAX_WEIGHTED_OUTCOMES)]
+    pub weights: Vec<u16>, //  36 — relative odds, indexed by outcome
+    #[max_len(MAX_WEIGHTED_OUTCOMES, MAX_OUTCOME_LABEL_LEN)]
+    pub labels: Vec<String>, // 580 — display name per outcome
+    pub bump: u8,          //   1 — PDA bump seed
+}
+
+impl WeightedTable {
+    /// Validate and store outcomes: 1 to `MAX_WEIGHTED_OUTCOMES` of them, one
+    /// label each, and a non-zero total weight.
+    pub fn set(&mut self, weights: Vec<u16>, labels: Vec<String>) -> Result<()> {
+        require!(
+            !weights.is_empty()
+                && weights.len() <= MAX_WEIGHTED_OUTCOMES
+                && labels.len() == weights.len()
+                && labels.iter().all(|l| l.len() <= MAX_OUTCOME_LABEL_LEN)
+                && weights.iter().any(|&w| w > 0),
+            VrfRarityError::InvalidWeightedTable
+        );
+        self.weights = weights;
+        self.labels = labels;
+        Ok(())
+    }
+
+    /// Store a rarity table's odds: each tier's width is its weight, so a
+    /// weighted pick lands on the tier `tier_for_roll` maps the same
+    /// randomness to.
+    pub fn set_rarity(&mut self, table: &RarityTable) -> Result<()> {
+        let thresholds = &table.thresholds[..table.tier_count as usize];
+        self.set(
+            (0..thresholds.len())
+                .map(|tier| rarity_math::tier_weight(thresholds, tier))
+                .collect(),
+            (0..thresholds.len() as u8).map(rarity_name).collect(),
+        )
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct WeightedRollResult {
+    pub player: Pubkey,       // 32 — wallet that requested the roll
+    pub nonce: u64,           //  8 — client-chosen nonce, PDA seed
+    pub table_id: u32,        //  4 — WeightedTable rolled against
+    pub table_version: u32,   //  4 — table version at request, required at callback
+    pub outcome: u8,          //  1 — index into the table's weights and labels
+    pub fulfilled: bool,      //  1 — true after VRF callback
+    pub randomness: [u8; 32], // 32 — raw VRF output
+    pub requested_slot: u64,  //  8 — slot of the request
+    pub fulfilled_slot: u64,  //  8 — slot of the callback
+    pub bump: u8,             //  1 — PDA bump seed
+}
+
+/// Marks a game id as submitted to the leaderboard for a player.
+#[account]
+#[derive(InitSpace)]
+pub struct ScoreSubmission {
+    pub player: Pubkey,    // 32 — wallet whose score was submitted
+    pub game_id: u64,      //  8 — game the score belongs to
+    pub score: u64,        //  8 — submitted floor price
+    pub submitted_at: i64, //  8 — unix timestamp of the submission
+    pub bump: u8,          //  1 — PDA bump seed
+}
+
+/// End-of-game stats for the human player, as reported by the client.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct GameSummary {
+    pub turns_played: u16,      //  2 — turns completed, at most MAX_TURNS
+    pub floor_price: u64,       //  8 — final floor price (score)
+    pub kill_count: u16,        //  2 — enemy units killed
+    pub wonders_built: u8,      //  1 — wonders completed
+    pub techs_researched: u8,   //  1 — technologies researched
+    pub settlements_owned: u16, //  2 — settlements held at game end
+    pub golden_ages: u8,        //  1 — golden ages triggered
+    pub great_people: u8,       //  1 — great people earned
+}
+
+impl GameSummary {
+    /// Reject stats the game rules cannot produce. The floor price must fall
+    /// between what the reported settlements, techs, kills and wonders alone
+    /// are worth and `floor_price_ceiling`.
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.turns_played >= 1 && self.turns_played <= MAX_TURNS,
+            VrfRarityError::InvalidGameSummary
+        );
+        require!(
+            self.techs_researched <= MAX_TECHS
+                && self.wonders_built <= MAX_WONDERS
+                && self.great_people <= MAX_GREAT_PEOPLE
+                && self.golden_ages <= MAX_GOLDEN_AGES
+                && self.settlements_owned as u64 <= MAP_TILES,
+            VrfRarityError::InvalidGameSummary
+        );
+
+        // calculateFloorPrice: 10 per settlement, 5 per tech, 3 per kill,
+        // at least 25 per wonder
+        let floor = 10 * self.settlements_owned as u64
+            + 5 * self.techs_researched as u64
+            + 3 * self.kill_count as u64
+            + 25 * self.wonders_built as u64;
+        require!(
+            self.floor_price >= floor && self.floor_price <= self.floor_price_ceiling(),
+            VrfRarityError::InvalidGameSummary
+        );
+        Ok(())
+    }
+
+    /// Most `calculateFloorPrice` can award given the reported settlements,
+    /// techs, kills and wonders, with every other term at its maximum.
+    pub fn floor_price_ceiling(&self) -> u64 {
+        let settlements = self.settlements_owned as u64;
+        // settlements at max level, whole map owned, every culture
+        let settlement_terms = (10 + 5 * MAX_SETTLEMENT_LEVEL) * settlements;
+        let map_terms = MAP_TILES + 5 * MAX_CULTURES;
+        // a full stack of legendary units on every tile
+        let unit_terms = MAP_TILES * MAX_UNITS_PER_TILE * MAX_UNIT_FLOOR_PRICE;
+        // just_scanning (+1 per 3 population), take_out_the_brooms (+5 per 10 tiles)
+        let policy_terms = settlements * MAX_SETTLEMENT_LEVEL / 3 + 5 * (MAP_TILES / 10);
+        settlement_terms
+            + map_terms
+            + unit_terms
+            + policy_terms
+            + 5 * self.techs_researched as u64
+            + 3 * self.kill_count as u64
+            + MAX_WONDER_FLOOR_PRICE * self.wonders_built as u64
+            + MAX_GOLD_FLOOR_PRICE_PER_TURN * self.turns_played as u64
+    }
+}
+
+/// A validated summary of one finished game.
+#[account]
+#[derive(InitSpace)]
+pub struct GameRecord {
+    pub player: Pubkey,       // 32 — wallet that played the game
+    pub game_id: u64,         //  8 — GameSession the record closes out
+    pub tribe: Tribe,         //  1 — tribe from the GameSession
+    pub summary: GameSummary, // 18 — validated end-of-game stats
+    pub finalized_at: i64,    //  8 — unix timestamp of finalize_game
+    pub bump: u8,             //  1 — PDA bump seed
+}
+
+/// Replay verifier keys whose signatures `attest_result` accepts.
+#[account]
+#[derive(InitSpace)]
+pub struct VerifierRegistry {
+    #[max_len(MAX_VERIFIERS)]
+    pub verifiers: Vec<Pubkey>, // 132 — allowlisted Ed25519 keys
+    pub bump: u8, //   1 — PDA bump seed
+}
+
+/// A score a replay verifier re-derived from the action log.
+#[account]
+#[derive(InitSpace)]
+pub struct AttestedResult {
+    pub player: Pubkey,            // 32 — wallet that played the game
+    pub game_id: u64,              //  8 — GameSession that was replayed
+    pub verifier: Pubkey,          // 32 — verifier key that signed
+    pub action_log_hash: [u8; 32], // 32 — hash of the replayed action log
+    pub score: u64,                //  8 — verified floor price
+    pub attested_at: i64,          //  8 — unix timestamp of the attestation
+    pub bump: u8,                  //  1 — PDA bump seed
+}
+
+/// Rolling hash over a game's per-turn action hashes. A replay verifier
+/// recomputes it from the action log to prove the log is the one played.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct ActionChain {
+    pub player: Pubkey, // 32 — wallet playing the game
+    pub game_id: u64,   //  8 — game the chain belongs to
+    pub last_turn: u16, //  2 — most recently committed turn, 0 before any
+    pub commits: u16,   //  2 — turns committed so far
+    pub head: [u8; 32], // 32 — current rolling hash
+    pub bump: u8,       //  1 — PDA bump seed
+}
+
+impl ActionChain {
+    /// Claim a freshly created chain for `player`.
+    pub fn init_if_new(&mut self, player: Pubkey, game_id: u64, bump: u8) {
+        if self.player == Pubkey::default() {
+            self.player = player;
+            self.game_id = game_id;
+            self.bump = bump;
+        }
+    }
+
+    /// Append `turn`, which must come after the last committed turn.
+    pub fn commit(&mut self, turn: u16, action_hash: &[u8; 32]) -> Result<()> {
+        require!(
+            turn > self.last_turn && turn <= MAX_TURNS,
+            VrfRarityError::InvalidTurn
+        );
+        self.head = chain_turn(&self.head, turn, action_hash);
+        self.last_turn = turn;
+        self.commits += 1;
+        Ok(())
+    }
+}
+
+/// `GameSummary` fields an achievement condition can test.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum SummaryStat {
+    TurnsPlayed,
+    FloorPrice,
+    KillCount,
+    WondersBuilt,
+    TechsResearched,
+    SettlementsOwned,
+    GoldenAges,
+    GreatPeople,
+}
+
+impl SummaryStat {
+    /// Read this stat from a summary.
+    pub fn value(self, summary: &GameSummary) -> u64 {
+        match self {
+            SummaryStat::TurnsPlayed => summary.turns_played as u64,
+            SummaryStat::FloorPrice => summary.floor_price,
+            SummaryStat::KillCount => summary.kill_count as u64,
+            SummaryStat::WondersBuilt => summary.wonders_built as u64,
+            SummaryStat::TechsResearched => summary.techs_researched as u64,
+            SummaryStat::SettlementsOwned => summary.settlements_owned as u64,
+            SummaryStat::GoldenAges => summary.golden_ages as u64,
+            SummaryStat::GreatPeople => summary.great_people as u64,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Comparison {
+    AtLeast,
+    AtMost,
+}
+
+/// `stat <comparison> threshold`, e.g. `KillCount AtLeast 10`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct AchievementCondition {
+    pub stat: SummaryStat,
+    pub comparison: Comparison,
+    pub threshold: u64,
+}
+
+impl AchievementCondition {
+    pub fn is_met(&self, summary: &GameSummary) -> bool {
+        let value = self.stat.value(summary);
+        match self.comparison {
+            Comparison::AtLeast => value >= self.threshold,
+            Comparison::AtMost => value <= self.threshold,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AchievementDefinition {
+    pub authority: Pubkey,               // 32 — may update the achievement
+    pub achievement_id: u16,             //  2 — PDA seed
+    pub condition: AchievementCondition, // 10 — what a game must reach
+    pub active: bool,                    //  1 — false once retired
+    pub bump: u8,                        //  1 — PDA bump seed
+    #[max_len(MAX_ACHIEVEMENT_SLUG_LEN)]
+    pub slug: String, // 36 — app id, e.g. "ten_kills"
+}
+
+/// Proof a player earned an achievement. One per player and achievement.
+#[account]
+#[derive(InitSpace)]
+pub struct AchievementUnlock {
+    pub player: Pubkey,      // 32 — wallet that earned it
+    pub achievement_id: u16, //  2 — AchievementDefinition that was met
+    pub game_id: u64,        //  8 — GameRecord it was earned in
+    pub unlocked_at: i64,    //  8 — unix timestamp of the claim
+    pub bump: u8,            //  1 — PDA bump seed
+}
+
+// ---- Events ----
+
+#[event]
+pub struct RarityRequested {
+    pub player: Pubkey,
+    pub nonce: u64,
+    pub result_pda: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct RarityFulfilled {
+    pub player: Pubkey,
+    pub nonce: u64,
+    pub rarity: u8,
+    pub roll_value: u16,
+    pub slot: u64,
+}
+
+#[event]
+pub struct RarityRollCancelled {
+    pub player: Pubkey,
+    pub nonce: u64,
+    pub requested_slot: u64,
+    pub cancelled_slot: u64,
+    pub skipped: u64,
+}
+
+#[event]
+pub struct RarityBatchCancelled {
+    pub player: Pubkey,
+    pub nonce: u64,
+    pub count: u8,
+    pub requested_slot: u64,
+    pub cancelled_slot: u64,
+    pub skipped: u64,
+}
+
+#[event]
+pub struct WeightedRollCancelled {
+    pub player: Pubkey,
+    pub nonce: u64,
+    pub table_id: u32,
+    pub requested_slot: u64,
+    pub cancelled_slot: u64,
+}
+
+#[event]
+pub struct RollConsumed {
+    pub player: Pubkey,
+    pub nonce: u64,
+    pub slot_index: u8,
+    pub unit_id: u32,
+    pub skipped: u64,
+}
+
+#[event]
+pub struct UnitCollectibleMinted {
+    pub player: Pubkey,
+    pub rarity_result: Pubkey,
+    pub mint: Pubkey,
+    pub rarity: u8,
+    pub unit_type: u8,
+}
+
+#[event]
+pub struct ScoreSubmitted {
+    pub player: Pubkey,
+    pub game_id: u64,
+    pub score: u64,
+}
+
+#[event]
+pub struct GameFinalized {
+    pub player: Pubkey,
+    pub game_id: u64,
+    pub floor_price: u64,
+    pub turns_played: u16,
+}
+
+#[event]
+pub struct ResultAttested {
+    pub player: Pubkey,
+    pub game_id: u64,
+    pub verifier: Pubkey,
+    pub score: u64,
+}
+
+#[event]
+pub struct TurnCommitted {
+    pub player: Pubkey,
+    pub game_id: u64,
+    pub turn: u16,
+    pub head: [u8; 32],
+}
+
+#[event]
+pub struct AchievementUnlocked {
+    pub player: Pubkey,
+    pub achievement_id: u16,
+    pub game_id: u64,
+}
+
+// ---- Helpers ----
+
+/// Caller-seed kinds, so requests of different kinds sharing a nonce never
+/// produce the same VRF request seed.
+const SEED_KIND_SINGLE: u8 = 0;
+const SEED_KIND_BATCH: u8 = 1;
+const SEED_KIND_GAME: u8 = 2;
+const SEED_KIND_LOOTBOX: u8 = 3;
+const SEED_KIND_GOLDEN_AGE: u8 = 4;
+const SEED_KIND_PROBABILITY: u8 = 5;
+const SEED_KIND_WEIGHTED: u8 = 6;
+
+/// Pad the nonce (and request kind) into a 32-byte caller seed.
+fn caller_seed(nonce: u64, kind: u8) -> [u8; 32] {
+    let mut seed = [0u8; 32];
+    seed[..8].copy_from_slice(&nonce.to_le_bytes());
+    seed[8] = kind;
+    seed
+}
+
+/// Derive an independent 32-byte value as `sha256(randomness || index)`, so
+/// one VRF output can drive several draws that each can be re-derived.
+fn sub_randomness(randomness: &[u8; 32], index: u8) -> [u8; 32] {
+    hashv(&[&randomness[..], &[index]]).to_bytes()
+}
+
+fn callback_meta(pubkey: Pubkey, is_writable: bool) -> SerializableAccountMeta {
+    SerializableAccountMeta {
+        pubkey,
+        is_signer: false,
+        is_writable,
+    }
+}
+
+/// Build a randomness request that calls back into this program.
+fn vrf_request_ix(
+    payer: Pubkey,
+    oracle_queue: Pubkey,
+    callback_discriminator: &[u8],
+    caller_seed: [u8; 32],
+    accounts_metas: Vec<SerializableAccountMeta>,
+) -> Instruction {
+    create_request_randomness_ix(RequestRandomnessParams {
+        payer,
+        oracle_queue,
+        callback_program_id: ID,
+        callback_discriminator: callback_discriminator.to_vec(),
+        caller_seed,
+        accounts_metas: Some(accounts_metas),
+        ..Default::default()
+    })
+}
+
+/// Number of golden age effects in `era`, `None` for an unknown era.
+fn golden_age_effect_count(era: u8) -> Option<u8> {
+    GOLDEN_AGE_EFFECTS_PER_ERA
+        .get((era as usize).checked_sub(1)?)
+        .copied()
+}
+
+/// Display name for a rarity tier; tiers past the launch table are numbered.
+fn rarity_name(rarity: u8) -> String {
+    RARITY_NAMES
+        .get(rarity as usize)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("Tier {rarity}"))
+}
+
+/// True if `data` is a baseline (v1) `RarityResult` owned by `player`:
+/// the v1 length, the account discriminator, then the player key.
+fn is_legacy_rarity_result(data: &[u8], player: &Pubkey) -> bool {
+    data.len() == LEGACY_RARITY_RESULT_LEN
+        && data[..8] == *RarityResult::DISCRIMINATOR
+        && data[8..40] == player.to_bytes()
+}
+
+/// SOAR `submit_score`. Account order follows SOAR's `SubmitScore`; the
+/// player pays for any score list growth.
+#[allow(clippy::too_many_arguments)]
+fn soar_submit_score_ix(
+    payer: Pubkey,
+    authority: Pubkey,
+    player_account: Pubkey,
+    game: Pubkey,
+    leaderboard: Pubkey,
+    player_scores: Pubkey,
+    top_entries: Pubkey,
+    score: u64,
+) -> Instruction {
+    let mut data = SOAR_SUBMIT_SCORE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&score.to_le_bytes());
+    Instruction {
+        program_id: SOAR_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new_readonly(player_account, false),
+            AccountMeta::new_readonly(game, false),
+            AccountMeta::new_readonly(leaderboard, false),
+            AccountMeta::new(player_scores, false),
+            AccountMeta::new(top_entries, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// The bytes a replay verifier signs for `attest_result`.
+pub fn attestation_message(
+    player: &Pubkey,
+    seed: &[u8; 32],
+    action_log_hash: &[u8; 32],
+    score: u64,
+) -> [u8; ATTESTATION_MESSAGE_LEN] {
+    let mut message = [0u8; ATTESTATION_MESSAGE_LEN];
+    message[..32].copy_from_slice(player.as_ref());
+    message[32..64].copy_from_slice(seed);
+    message[64..96].copy_from_slice(action_log_hash);
+    message[96..].copy_from_slice(&score.to_le_bytes());
+    message
+}
+
+/// Check that `ix` is an Ed25519 precompile instruction verifying exactly
+/// one signature over `message`, with key, signature and message all inline,
+/// and return the signing key. The precompile has already checked the
+/// signature itself by the time this runs.
+fn ed25519_signer(ix: &Instruction, message: &[u8]) -> Result<Pubkey> {
+    const HEADER_LEN: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+
+    let data = &ix.data;
+    require!(
+        ix.program_id == ed25519_program::ID
+            && data.len() >= HEADER_LEN + OFFSETS_LEN
+            && data[0] == 1,
+        VrfRarityError::InvalidAttestation
+    );
+
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+    let offsets = HEADER_LEN;
+    let signature_ix = read_u16(offsets + 2);
+    let pubkey_offset = read_u16(offsets + 4);
+    let pubkey_ix = read_u16(offsets + 6);
+    let message_offset = read_u16(offsets + 8);
+    let message_len = read_u16(offsets + 10);
+    let message_ix = read_u16(offsets + 12);
+
+    // u16::MAX means "this instruction"; anything else points elsewhere
+    let inline = u16::MAX as usize;
+    require!(
+        signature_ix == inline && pubkey_ix == inline && message_ix == inline,
+        VrfRarityError::InvalidAttestation
+    );
+    require!(
+        message_len == message.len()
+            && data.get(message_offset..message_offset + message_len) == Some(message),
+        VrfRarityError::InvalidAttestation
+    );
+
+    let pubkey = data
+        .get(pubkey_offset..pubkey_offset + 32)
+        .ok_or(VrfRarityError::InvalidAttestation)?;
+    Ok(Pubkey::new_from_array(pubkey.try_into().unwrap()))
+}
+
+/// One step of the action chain: `sha256(head || turn_le || action_hash)`.
+pub fn chain_turn(head: &[u8; 32], turn: u16, action_hash: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&head[..], &turn.to_le_bytes(), &action_hash[..]]).to_bytes()
+}
+
+// ---- Errors ----
+
+#[error_code]
+pub enum VrfRarityError {
+    #[msg("Rarity roll has not been fulfilled by the oracle yet")]
+    RollNotFulfilled,
+    #[msg("Signer is not allowed to perform this action")]
+    Unauthorized,
+    #[msg("Rarity table must have 1-8 strictly increasing bounds ending at 9999")]
+    InvalidRarityTable,
+    #[msg("Batch roll count must be between 1 and 10")]
+    InvalidBatchCount,
+    #[msg("Rarity roll has already been fulfilled")]
+    AlreadyFulfilled,
+    #[msg("Rarity result does not belong to this player")]
+    PlayerMismatch,
+    #[msg("Resolved rarity index is outside the rarity table")]
+    InvalidRarityIndex,
+    #[msg("Nonce has already been used for a roll by this player")]
+    InvalidNonce,
+    #[msg("Rarity roll is still within its fulfillment window")]
+    RollNotStale,
+    #[msg("Map size and player count must be within the game rules")]
+    InvalidGameSettings,
+    #[msg("A game session with this id already exists")]
+    GameAlreadyStarted,
+    #[msg("Turn does not match the game context")]
+    InvalidTurn,
+    #[msg("Roll has already been consumed")]
+    RollAlreadyConsumed,
+    #[msg("Batch slots must be consumed in order")]
+    InvalidBatchSlot,
+    #[msg("Era must be between 1 and 3")]
+    InvalidEra,
+    #[msg("Probability must have a non-zero denominator and numerator <= denominator")]
+    InvalidProbability,
+    #[msg("Only Epic or Legendary rolls can back a collectible")]
+    RarityTooLow,
+    #[msg("Weighted table needs 1-16 labelled outcomes with a non-zero total weight")]
+    InvalidWeightedTable,
+    #[msg("Score must be non-zero")]
+    InvalidScore,
+    #[msg("Game summary breaks the game rules")]
+    InvalidGameSummary,
+    #[msg("Verifier registry holds at most 4 keys")]
+    TooManyVerifiers,
+    #[msg("Missing or malformed Ed25519 attestation")]
+    InvalidAttestation,
+    #[msg("Attestation was not signed by an allowlisted verifier")]
+    VerifierNotAllowed,
+    #[msg("Achievement slug must be 1-32 bytes")]
+    InvalidAchievement,
+    #[msg("Achievement has been retired")]
+    AchievementInactive,
+    #[msg("Game record does not meet the achievement condition")]
+    AchievementNotEarned,
+    #[msg("Account is not a baseline-layout rarity result for this player")]
+    NotLegacyResult,
+    #[msg("Weighted table changed while the roll was in flight")]
+    WeightedTableChanged,
+    #[msg("Rarity config changed while the roll was in flight")]
+    RarityConfigChanged,
+    #[msg("Current turn is not committed to the action chain")]
+    TurnNotCommitted,
+    #[msg("Lootbox id is out of range or every lootbox is opened")]
+    InvalidLootbox,
+    #[msg("Weighted table id is reserved for rarity tables")]
+    ReservedWeightedTable,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launch_table() -> RarityTable {
+        RarityTable::new(&DEFAULT_RARITY_THRESHOLDS).unwrap()
+    }
+
+    #[test]
+    fn pity_forces_rare_after_threshold_misses() {
+        let table = launch_table();
+        let mut stats = PlayerRollStats::default();
+        for miss in 0..DEFAULT_PITY_THRESHOLD as u16 {
+            assert_eq!(
+                stats.apply_roll(0, DEFAULT_PITY_THRESHOLD, &table),
+                (0, false)
+            );
+            assert_eq!(stats.consecutive_misses, miss + 1);
+        }
+        assert_eq!(
+            stats.apply_roll(1, DEFAULT_PITY_THRESHOLD, &table),
+            (PITY_MIN_RARITY, true)
+        );
+        assert_eq!(stats.consecutive_misses, 0);
+        assert_eq!(stats.pity_triggers, 1);
+        assert_eq!(stats.total_rolls, DEFAULT_PITY_THRESHOLD as u64 + 1);
+    }
+
+    #[test]
+    fn threshold_of_one_forces_every_other_roll() {
+        let table = launch_table();
+        let mut stats = PlayerRollStats::default();
+        assert_eq!(stats.apply_roll(0, 1, &table), (0, false));
+        assert_eq!(stats.apply_roll(0, 1, &table), (PITY_MIN_RARITY, true));
+        assert_eq!(stats.apply_roll(0, 1, &table), (0, false));
+    }
+
+    #[test]
+    fn rare_or_better_resets_the_streak() {
+        let table = launch_table();
+        let mut stats = PlayerRollStats::default();
+        stats.apply_roll(0, DEFAULT_PITY_THRESHOLD, &table);
+        stats.apply_roll(1, DEFAULT_PITY_THRESHOLD, &table);
+        assert_eq!(stats.consecutive_misses, 2);
+        assert_eq!(
+            stats.apply_roll(4, DEFAULT_PITY_THRESHOLD, &table),
+            (4, false)
+        );
+        assert_eq!(stats.consecutive_misses, 0);
+        assert_eq!(stats.pity_triggers, 0);
+    }
+
+    #[test]
+    fn saturated_streak_with_pity_disabled_does_not_overflow() {
+        let table = launch_table();
+        let mut stats = PlayerRollStats {
+            consecutive_misses: u16::MAX,
+            ..Default::default()
+        };
+        assert_eq!(stats.apply_roll(0, 0, &table), (0, false));
+        assert_eq!(stats.consecutive_misses, u16::MAX);
+        assert_eq!(
+            stats.apply_roll(0, DEFAULT_PITY_THRESHOLD, &table),
+            (PITY_MIN_RARITY, true)
+        );
+    }
+
+    #[test]
+    fn pity_tier_is_capped_by_small_tables() {
+        let two_tiers = RarityTable::new(&[8_999, ROLL_MAX]).unwrap();
+        let mut stats = PlayerRollStats {
+            consecutive_misses: 3,
+            ..Default::default()
+        };
+        assert_eq!(stats.apply_roll(0, 3, &two_tiers), (1, true));
+
+        // A single tier has nothing to raise a roll to
+        let one_tier = RarityTable::new(&[ROLL_MAX]).unwrap();
+        let mut stats = PlayerRollStats {
+            consecutive_misses: 3,
+            ..Default::default()
+        };
+        assert_eq!(stats.apply_roll(0, 3, &one_tier), (0, false));
+        assert_eq!(stats.consecutive_misses, 0);
+    }
+
+    #[test]
+    fn rarity_table_pads_unused_tiers_with_roll_max() {
+        let table = RarityTable::new(&[999, ROLL_MAX]).unwrap();
+        assert_eq!(table.tier_count, 2);
+        assert_eq!(table.thresholds[..3], [999, ROLL_MAX, ROLL_MAX]);
+        assert_eq!(table.tier_for_roll(999), 0);
+        assert_eq!(table.tier_for_roll(1_000), 1);
+
+        let full = [1, 2, 3, 4, 5, 6, 7, ROLL_MAX];
+        assert_eq!(RarityTable::new(&full).unwrap().thresholds, full);
+    }
+
+    #[test]
+    fn rarity_table_rejects_invalid_thresholds() {
+        for thresholds in [
+            &[][..],
+            &[ROLL_MAX - 1],
+            &[5_000, 5_000, ROLL_MAX],
+            &[7_999, 4_999, ROLL_MAX],
+            &[4_999, ROLL_MAX, ROLL_MAX],
+            &[1, 2, 3, 4, 5, 6, 7, 8, ROLL_MAX],
+        ] {
+            assert!(
+                RarityTable::new(thresholds).is_err(),
+                "{thresholds:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn game_context_era_only_moves_forward_within_known_eras() {
+        let mut context = GameContext {
+            current_turn: 1,
+            era: 1,
+            ..Default::default()
+        };
+        assert!(context.advance(2, 0, 1).is_err());
+        assert!(context.advance(2, 4, 1).is_err());
+        context.advance(2, 3, 1).unwrap();
+        assert_eq!(context.era, 3);
+        assert!(context.advance(3, 2, 2).is_err());
+        context.advance(3, 3, 2).unwrap();
+        assert_eq!((context.current_turn, context.era), (3, 3));
+    }
+
+    #[test]
+    fn session_opens_each_lootbox_id_in_range() {
+        let mut session = GameSession::default();
+        assert!(session.open_lootbox(0).is_err());
+        assert!(session.open_lootbox(MAX_LOOTBOXES as u32 + 1).is_err());
+        assert!(session.open_lootbox(u32::MAX).is_err());
+        for id in 1..=MAX_LOOTBOXES as u32 {
+            session.open_lootbox(id).unwrap();
+        }
+        assert_eq!(session.lootboxes_opened, MAX_LOOTBOXES);
+        // The LootboxResult PDA rejects a repeated id; the counter caps the rest
+        assert!(session.open_lootbox(1).is_err());
+    }
+
+    #[test]
+    fn game_context_advances_only_past_committed_turns() {
+        let mut context = GameContext {
+            current_turn: 1,
+            era: 1,
+            ..Default::default()
+        };
+        // Turn 1 not committed yet
+        assert!(context.advance(2, 1, 0).is_err());
+        context.advance(2, 1, 1).unwrap();
+        assert_eq!(context.current_turn, 2);
+
+        // No skipping ahead or going back, even once committed
+        assert!(context.advance(4, 1, 2).is_err());
+        assert!(context.advance(2, 1, 2).is_err());
+        // A chain ahead of the context does not commit the current turn
+        assert!(context.advance(3, 1, 5).is_err());
+
+        context.current_turn = MAX_TURNS;
+        assert!(context.advance(MAX_TURNS + 1, 1, MAX_TURNS).is_err());
+    }
+
+    fn labels(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("outcome {i}")).collect()
+    }
+
+    #[test]
+    fn weighted_table_accepts_labelled_outcomes_up_to_the_limit() {
+        let mut table = WeightedTable::default();
+        table.set(vec![0, 3], labels(2)).unwrap();
+        assert_eq!(table.weights, [0, 3]);
+
+        let long_label = "x".repeat(MAX_OUTCOME_LABEL_LEN);
+        table.set(vec![1], vec![long_label]).unwrap();
+        table
+            .set(
+                vec![1; MAX_WEIGHTED_OUTCOMES],
+                labels(MAX_WEIGHTED_OUTCOMES),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn weighted_table_rejects_invalid_outcomes() {
+        let too_many = MAX_WEIGHTED_OUTCOMES + 1;
+        for (weights, labels) in [
+            (vec![], vec![]),
+            (vec![1; too_many], labels(too_many)),
+            (vec![1, 2], labels(1)),
+            (vec![1], labels(2)),
+            (vec![1], vec!["x".repeat(MAX_OUTCOME_LABEL_LEN + 1)]),
+            (vec![0, 0], labels(2)),
+        ] {
+            let mut table = WeightedTable::default();
+            assert!(table.set(weights.clone(), labels).is_err(), "{weights:?}");
+            assert!(table.weights.is_empty());
+        }
+    }
+
+    #[test]
+    fn rarity_weighted_table_picks_the_same_tier_as_the_config() {
+        let table = launch_table();
+        let mut weighted = WeightedTable::default();
+        weighted.set_rarity(&table).unwrap();
+        assert_eq!(weighted.weights, rarity_math::RARITY_WEIGHTS);
+        assert_eq!(weighted.labels, RARITY_NAMES);
+
+        for seed in 0..=255u8 {
+            let randomness = [seed; 32];
+            let roll = rarity_math::roll_from_randomness(&randomness);
+            assert_eq!(
+                rarity_math::pick_weighted(&weighted.weights, &randomness),
+                Some(table.tier_for_roll(roll) as usize)
+            );
+        }
+    }
+
+    /// `sha256([0; 32] || 1u16_le || [0xab; 32])`, computed independently.
+    const FIRST_CHAIN_HEAD: [u8; 32] = [
+        121, 240, 40, 110, 84, 34, 255, 89, 146, 139, 183, 133, 193, 71, 38, 213, 79, 118, 204,
+        147, 247, 77, 50, 99, 50, 246, 118, 40, 145, 170, 14, 108,
+    ];
+
+    #[test]
+    fn chain_turn_matches_the_pinned_vector() {
+        assert_eq!(chain_turn(&[0; 32], 1, &[0xab; 32]), FIRST_CHAIN_HEAD);
+    }
+
+    #[test]
+    fn action_chain_folds_commits_in_order() {
+        let mut chain = ActionChain::default();
+        chain.commit(1, &[0xab; 32]).unwrap();
+        assert_eq!(chain.head, FIRST_CHAIN_HEAD);
+        chain.commit(3, &[0xcd; 32]).unwrap();
+        assert_eq!(chain.head, chain_turn(&FIRST_CHAIN_HEAD, 3, &[0xcd; 32]));
+        assert_eq!((chain.last_turn, chain.commits), (3, 2));
+
+        // The same hashes in another order give another head
+        let mut swapped = ActionChain::default();
+        swapped.commit(1, &[0xcd; 32]).unwrap();
+        swapped.commit(3, &[0xab; 32]).unwrap();
+        assert_ne!(swapped.head, chain.head);
+    }
+
+    #[test]
+    fn action_chain_rejects_turns_out_of_order() {
+        let mut chain = ActionChain::default();
+        assert!(chain.commit(0, &[1; 32]).is_err());
+        chain.commit(2, &[1; 32]).unwrap();
+        let head = chain.head;
+        for turn in [1, 2, MAX_TURNS + 1] {
+            assert!(chain.commit(turn, &[2; 32]).is_err(), "turn {turn}");
+        }
+        assert_eq!((chain.head, chain.last_turn, chain.commits), (head, 2, 1));
+        chain.commit(MAX_TURNS, &[2; 32]).unwrap();
+    }
+
+    #[test]
+    fn ledger_assigns_nonces_in_sequence() {
+        let mut ledger = RollLedger::default();
+        ledger.assign(0).unwrap();
+        ledger.assign(1).unwrap();
+        assert!(ledger.assign(1).is_err());
+        assert!(ledger.assign(5).is_err());
+        assert_eq!(ledger.next_nonce, 2);
+    }
+
+    #[test]
+    fn ledger_consumes_in_order_without_skips() {
+        let mut ledger = RollLedger::default();
+        for nonce in 0..3 {
+            assert_eq!(ledger.consume(nonce).unwrap(), 0);
+        }
+        assert_eq!(ledger.rolls_consumed, 3);
+        assert_eq!(ledger.rolls_skipped, 0);
+        assert_eq!(ledger.next_consume_nonce, 3);
+    }
+
+    #[test]
+    fn ledger_counts_gaps_as_skipped() {
+        let mut ledger = RollLedger::default();
+        assert_eq!(ledger.consume(0).unwrap(), 0);
+        assert_eq!(ledger.consume(3).unwrap(), 2);
+        assert_eq!(ledger.consume(7).unwrap(), 3);
+        assert_eq!(ledger.rolls_consumed, 3);
+        assert_eq!(ledger.rolls_skipped, 5);
+        assert_eq!(ledger.next_consume_nonce, 8);
+    }
+
+    #[test]
+    fn ledger_rejects_nonces_already_passed() {
+        let mut ledger = RollLedger::default();
+        ledger.consume(4).unwrap();
+        // Both the consumed nonce and the ones it skipped are closed
+        assert!(ledger.consume(4).is_err());
+        assert!(ledger.consume(2).is_err());
+        assert_eq!(ledger.rolls_consumed, 1);
+        assert_eq!(ledger.rolls_skipped, 4);
+    }
+
+    #[test]
+    fn ledger_moves_past_cancelled_nonces() {
+        let mut ledger = RollLedger::default();
+        ledger.consume(0).unwrap();
+        assert_eq!(ledger.cancel(1), 0);
+        assert_eq!(ledger.next_consume_nonce, 2);
+        assert!(ledger.consume(1).is_err());
+        assert_eq!(ledger.consume(2).unwrap(), 0);
+        assert_eq!(ledger.rolls_consumed, 2);
+        assert_eq!(ledger.rolls_skipped, 0);
+
+        // Gaps before a cancelled nonce are still skips
+        assert_eq!(ledger.cancel(5), 2);
+        assert_eq!(ledger.consume(6).unwrap(), 0);
+        assert_eq!(ledger.rolls_skipped, 2);
+    }
+
+    #[test]
+    fn ledger_takes_cancelled_nonces_out_of_the_skips() {
+        let mut ledger = RollLedger::default();
+        // Nonce 1 is still in flight when nonce 3 is consumed
+        assert_eq!(ledger.consume(3).unwrap(), 3);
+        assert_eq!(ledger.cancel(1), 0);
+        assert_eq!(ledger.rolls_skipped, 2);
+        assert_eq!(ledger.next_consume_nonce, 4);
+    }
+
+    #[test]
+    fn batch_slot_zero_consumes_the_batch_nonce() {
+        let mut ledger = RollLedger::default();
+        ledger.consume(0).unwrap();
+        let mut batch = RarityBatchResult {
+            nonce: 2,
+            count: 3,
+            ..Default::default()
+        };
+
+        assert!(batch.consume_slot(1, 11, &mut ledger).is_err());
+        assert_eq!(batch.consume_slot(0, 10, &mut ledger).unwrap(), 1);
+        assert_eq!(ledger.next_consume_nonce, 3);
+        assert_eq!(batch.consume_slot(1, 11, &mut ledger).unwrap(), 0);
+        assert_eq!(batch.consume_slot(2, 12, &mut ledger).unwrap(), 0);
+        assert!(batch.consume_slot(3, 13, &mut ledger).is_err());
+
+        // Later slots never touch the ledger again
+        assert_eq!(ledger.rolls_consumed, 2);
+        assert_eq!(ledger.rolls_skipped, 1);
+        assert_eq!(batch.unit_ids[..3], [10, 11, 12]);
+        assert_eq!(batch.consumed_count, 3);
+    }
+
+    fn legacy_result(player: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0; LEGACY_RARITY_RESULT_LEN];
+        data[..8].copy_from_slice(RarityResult::DISCRIMINATOR);
+        data[8..40].copy_from_slice(player.as_ref());
+        data
+    }
+
+    #[test]
+    fn legacy_result_matches_discriminator_player_and_length() {
+        let player = Pubkey::new_from_array([5; 32]);
+        assert!(is_legacy_rarity_result(&legacy_result(&player), &player));
+
+        // Another player's result
+        let other = Pubkey::new_from_array([6; 32]);
+        assert!(!is_legacy_rarity_result(&legacy_result(&other), &player));
+
+        // A current-layout result, or any other account type
+        let mut current = legacy_result(&player);
+        current.resize(8 + RarityResult::INIT_SPACE, 0);
+        assert!(!is_legacy_rarity_result(&current, &player));
+        let mut other_account = legacy_result(&player);
+        other_account[..8].copy_from_slice(RollLedger::DISCRIMINATOR);
+        assert!(!is_legacy_rarity_result(&other_account, &player));
+
+        assert!(!is_legacy_rarity_result(&[], &player));
+    }
+
+    fn summary() -> GameSummary {
+        GameSummary {
+            turns_played: 30,
+            floor_price: 400,
+            kill_count: 10,
+            wonders_built: 2,
+            techs_researched: 12,
+            settlements_owned: 4,
+            golden_ages: 1,
+            great_people: 2,
+        }
+    }
+
+    #[test]
+    fn summary_turns_must_be_within_the_game() {
+        assert!(summary().validate().is_ok());
+        for (turns, ok) in [
+            (0, false),
+            (1, true),
+            (MAX_TURNS, true),
+            (MAX_TURNS + 1, false),
+        ] {
+            let summary = GameSummary {
+                turns_played: turns,
+                ..summary()
+            };
+            assert_eq!(summary.validate().is_ok(), ok, "turns_played {turns}");
+        }
+    }
+
+    type SetCount = fn(&mut GameSummary, u16);
+
+    #[test]
+    fn summary_counts_are_capped_by_content() {
+        let bounded: [(SetCount, u16); 5] = [
+            (|s, n| s.techs_researched = n as u8, MAX_TECHS as u16),
+            (|s, n| s.wonders_built = n as u8, MAX_WONDERS as u16),
+            (|s, n| s.great_people = n as u8, MAX_GREAT_PEOPLE as u16),
+            (|s, n| s.golden_ages = n as u8, MAX_GOLDEN_AGES as u16),
+            (|s, n| s.settlements_owned = n, MAP_TILES as u16),
+        ];
+        for (set, max) in bounded {
+            let mut at_max = summary();
+            set(&mut at_max, max);
+            at_max.floor_price = at_max.floor_price_ceiling();
+            assert!(at_max.validate().is_ok(), "{max} should be accepted");
+
+            let mut over = at_max;
+            set(&mut over, max + 1);
+            over.floor_price = over.floor_price_ceiling();
+            assert!(over.validate().is_err(), "{} should be rejected", max + 1);
+        }
+    }
+
+    #[test]
+    fn summary_floor_price_must_cover_reported_stats() {
+        // 4 settlements, 12 techs, 10 kills, 2 wonders
+        let floor = 10 * 4 + 5 * 12 + 3 * 10 + 25 * 2;
+        let at_floor = GameSummary {
+            floor_price: floor,
+            ..summary()
+        };
+        assert!(at_floor.validate().is_ok());
+        let below = GameSummary {
+            floor_price: floor - 1,
+            ..summary()
+        };
+        assert!(below.validate().is_err());
+    }
+
+    #[test]
+    fn summary_floor_price_is_capped_by_the_ceiling() {
+        let ceiling = summary().floor_price_ceiling();
+        let at_ceiling = GameSummary {
+            floor_price: ceiling,
+            ..summary()
+        };
+        assert!(at_ceiling.validate().is_ok());
+        let above = GameSummary {
+            floor_price: ceiling + 1,
+            ..summary()
+        };
+        assert!(above.validate().is_err());
+    }
+
+    #[test]
+    fn ceiling_grows_with_reported_stats_and_turns() {
+        let base = summary().floor_price_ceiling();
+        let more_turns = GameSummary {
+            turns_played: 31,
+            ..summary()
+        };
+        assert_eq!(
+            more_turns.floor_price_ceiling(),
+            base + MAX_GOLD_FLOOR_PRICE_PER_TURN
+        );
+        let more_settlements = GameSummary {
+            settlements_owned: 7,
+            ..summary()
+        };
+        // 3 more settlements at 135 each, plus 75 population / 3 for just_scanning
+        assert_eq!(more_settlements.floor_price_ceiling(), base + 3 * 135 + 25);
+    }
+
+    fn condition(
+        stat: SummaryStat,
+        comparison: Comparison,
+        threshold: u64,
+    ) -> AchievementCondition {
+        AchievementCondition {
+            stat,
+            comparison,
+            threshold,
+        }
+    }
+
+    #[test]
+    fn achievement_condition_thresholds_are_inclusive() {
+        // summary() has 10 kills
+        for (comparison, threshold, met) in [
+            (Comparison::AtLeast, 9, true),
+            (Comparison::AtLeast, 10, true),
+            (Comparison::AtLeast, 11, false),
+            (Comparison::AtMost, 9, false),
+            (Comparison::AtMost, 10, true),
+            (Comparison::AtMost, 11, true),
+        ] {
+            let condition = condition(SummaryStat::KillCount, comparison, threshold);
+            assert_eq!(condition.is_met(&summary()), met, "threshold {threshold}");
+        }
+    }
+
+    #[test]
+    fn achievement_condition_reads_its_own_stat() {
+        // 30 turns, 2 wonders: only the stat named by the condition counts
+        let fast = condition(SummaryStat::TurnsPlayed, Comparison::AtMost, 30);
+        let builder = condition(SummaryStat::WondersBuilt, Comparison::AtLeast, 3);
+        assert!(fast.is_met(&summary()));
+        assert!(!builder.is_met(&summary()));
+        let more_wonders = GameSummary {
+            wonders_built: 3,
+            ..summary()
+        };
+        assert!(builder.is_met(&more_wonders));
+    }
+
+    const SIGNER: Pubkey = Pubkey::new_from_array([7; 32]);
+    const PUBKEY_OFFSET: u16 = 16;
+    const SIGNATURE_OFFSET: u16 = 48;
+    const MESSAGE_OFFSET: u16 = 112;
+
+    /// Ed25519 precompile data with one signature and everything inline:
+    /// header, offsets, pubkey, signature, message.
+    fn ed25519_ix(message: &[u8]) -> Instruction {
+        let mut data = vec![1, 0];
+        for field in [
+            SIGNATURE_OFFSET,
+            u16::MAX,
+            PUBKEY_OFFSET,
+            u16::MAX,
+            MESSAGE_OFFSET,
+            message.len() as u16,
+            u16::MAX,
+        ] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(SIGNER.as_ref());
+        data.extend_from_slice(&[9; 64]);
+        data.extend_from_slice(message);
+        Instruction {
+            program_id: ed25519_program::ID,
+            accounts: vec![],
+            data,
+        }
+    }
+
+    fn set_u16(ix: &mut Instruction, at: usize, value: u16) {
+        ix.data[at..at + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn ed25519_signer_accepts_an_inline_instruction() {
+        let message = attestation_message(&SIGNER, &[1; 32], &[2; 32], 500);
+        assert_eq!(
+            ed25519_signer(&ed25519_ix(&message), &message).unwrap(),
+            SIGNER
+        );
+    }
+
+    #[test]
+    fn ed25519_signer_rejects_data_from_other_instructions() {
+        let message = [3; ATTESTATION_MESSAGE_LEN];
+        // signature, pubkey and message instruction indices
+        for at in [4, 8, 14] {
+            let mut ix = ed25519_ix(&message);
+            set_u16(&mut ix, at, 0);
+            assert!(ed25519_signer(&ix, &message).is_err(), "index at {at}");
+        }
+    }
+
+    #[test]
+    fn ed25519_signer_requires_exactly_one_signature() {
+        let message = [3; ATTESTATION_MESSAGE_LEN];
+        for count in [0, 2] {
+            let mut ix = ed25519_ix(&message);
+            ix.data[0] = count;
+            assert!(ed25519_signer(&ix, &message).is_err());
+        }
+    }
+
+    #[test]
+    fn ed25519_signer_rejects_a_different_message() {
+        let message = [3; ATTESTATION_MESSAGE_LEN];
+        let ix = ed25519_ix(&message);
+        assert!(ed25519_signer(&ix, &[4; ATTESTATION_MESSAGE_LEN]).is_err());
+
+        let mut short = ed25519_ix(&message);
+        set_u16(&mut short, 12, ATTESTATION_MESSAGE_LEN as u16 - 1);
+        assert!(ed25519_signer(&short, &message).is_err());
+
+        let mut shifted = ed25519_ix(&message);
+        set_u16(&mut shifted, 10, MESSAGE_OFFSET + 1);
+        assert!(ed25519_signer(&shifted, &message).is_err());
+    }
+
+    #[test]
+    fn ed25519_signer_rejects_an_out_of_range_pubkey() {
+        let message = [3; ATTESTATION_MESSAGE_LEN];
+        let mut ix = ed25519_ix(&message);
+        let past_end = ix.data.len() as u16 - 31;
+        set_u16(&mut ix, 6, past_end);
+        assert!(ed25519_signer(&ix, &message).is_err());
+    }
+
+    #[test]
+    fn ed25519_signer_rejects_other_programs() {
+        let message = [3; ATTESTATION_MESSAGE_LEN];
+        let mut ix = ed25519_ix(&message);
+        ix.program_id = ID;
+        assert!(ed25519_signer(&ix, &message).is_err());
+    }
 }